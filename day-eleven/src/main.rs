@@ -37,20 +37,12 @@ fn rotate_robo(dir: &mut Dir, val: i32) {
     }
 }
 
-fn get_robo_output(robo: &mut intcode::IntcodeComp) -> Option<(Color, u8)> {
-    let maybe_color = robo.pop_output();
-    let maybe_dir = robo.pop_output();
-    if maybe_color == None && maybe_dir == None {
-        return None;
-    } else if maybe_dir == None {
-        panic!("Output should always come in pairs!");
-    }
-    let color = match num::FromPrimitive::from_i64(maybe_color.unwrap()) {
+fn to_color(val: i64) -> Color {
+    match num::FromPrimitive::from_i64(val) {
         Some(Color::BLACK) => Color::BLACK,
         Some(Color::WHITE) => Color::WHITE,
-        _ => panic!("Unexpected color value {:?}!", maybe_color.unwrap()),
-    };
-    return Some((color, maybe_dir.unwrap() as u8));
+        _ => panic!("Unexpected color value {:?}!", val),
+    }
 }
 
 fn get_paint_job(min_x: i32, max_x: i32, min_y: i32, max_y: i32, panels: HashMap<(i32, i32), Color>) {
@@ -80,8 +72,7 @@ fn get_paint_job(min_x: i32, max_x: i32, min_y: i32, max_y: i32, panels: HashMap
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-eleven/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-eleven/input.txt").expect("couldn't load tape");
 
     let mut robo_pos: (i32, i32) = (0, 0);
     let mut robo_dir: Dir = Dir::UP;
@@ -92,51 +83,42 @@ fn main() {
     let mut max_x = 0;
     let mut min_y = 0;
     let mut max_y = 0;
-    robo.start();
-    while *robo.state() != intcode::IntcodeState::Finished {
-        match robo.state() {
-            intcode::IntcodeState::NeedsInput => {
-                let mut cur_color = Color::BLACK;
-                if first_panel {
-                    cur_color = Color::WHITE;
-                    first_panel = false;
+    loop {
+        match robo.run_until_outputs() {
+            Ok(Some([color, turn])) => {
+                panels.insert(robo_pos, to_color(color));
+                rotate_robo(&mut robo_dir, turn as i32);
+                move_robo(&mut robo_pos, &robo_dir);
+                if robo_pos.0 < min_x {
+                    min_x = robo_pos.0;
+                }
+                if robo_pos.0 > max_x {
+                    max_x = robo_pos.0;
+                }
+                if robo_pos.1 < min_y {
+                    min_y = robo_pos.1;
                 }
-                match panels.get(&robo_pos) {
-                    Some(color) => cur_color = *color,
-                    None => (),
+                if robo_pos.1 > max_y {
+                    max_y = robo_pos.1;
                 }
-                robo.push_input(cur_color as i64);
-                robo.start();
             },
-            intcode::IntcodeState::Err(s) => panic!("Unexpected error: {}", s),
-            _ => (),
-        }
-        // robo.start() blocks until the computer either finishes or needs
-        // input.  Either way, we should take the opportunity to update any
-        // panels based on any output the computer has generated up until
-        // this point.
-        let mut maybe_output = get_robo_output(&mut robo);
-        loop {
-            if maybe_output.is_none() {
-                break;
-            }
-            let (color, dir) = maybe_output.unwrap();
-            panels.insert(robo_pos.clone(), color);
-            rotate_robo(&mut robo_dir, dir as i32);
-            maybe_output = get_robo_output(&mut robo);
-        }
-        move_robo(&mut robo_pos, &robo_dir);
-        if robo_pos.0 < min_x {
-            min_x = robo_pos.0;
-        }
-        if robo_pos.0 > max_x {
-            max_x = robo_pos.0;
-        }
-        if robo_pos.1 < min_y {
-            min_y = robo_pos.1;
-        }
-        if robo_pos.1 > max_y {
-            max_y = robo_pos.1;
+            Ok(None) => match robo.state() {
+                intcode::IntcodeState::NeedsInput => {
+                    let mut cur_color = Color::BLACK;
+                    if first_panel {
+                        cur_color = Color::WHITE;
+                        first_panel = false;
+                    }
+                    if let Some(color) = panels.get(&robo_pos) {
+                        cur_color = *color;
+                    }
+                    robo.push_input(cur_color as i64);
+                },
+                intcode::IntcodeState::Finished => break,
+                intcode::IntcodeState::Err(s) => panic!("Unexpected error: {}", s),
+                s => panic!("Unexpected state: {:?}", s),
+            },
+            Err(e) => panic!("{}", e),
         }
     }
 
@@ -1,106 +1,52 @@
 use std::collections::HashSet;
 use std::collections::VecDeque;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum PathResult {
-    Invalid,
-    Valid,
-    Oxygen((i32, i32)),
-}
-
-fn is_path_valid(path: &Vec<u8>, robo: &mut intcode::IntcodeComp, map: &mut HashSet<(i32, i32)>) -> PathResult {
-    let mut backtrack: VecDeque<u8> = VecDeque::new();
-    let mut path_result = PathResult::Valid;
-    let mut pos: (i32, i32) = (0, 0);
-    for m in path {
-        robo.push_input(*m as i64);
-        robo.start();
-        let result = robo.pop_output().unwrap();
-        if result == 0 {
-            path_result = PathResult::Invalid;
-            break;
-        }
-        match m {
-            1 => {
-                pos.1 += 1;
-                backtrack.push_front(2);
-            },
-            2 => {
-                pos.1 -= 1;
-                backtrack.push_front(1);
-            },
-            3 => {
-                pos.0 -= 1;
-                backtrack.push_front(4);
-            },
-            4 => {
-                pos.0 += 1;
-                backtrack.push_front(3);
-            },
-            _ => panic!("unknown direction"),
-        }
-        match result {
-            1 => path_result = PathResult::Valid,
-            2 => path_result = PathResult::Oxygen(pos.clone()),
-            _ => panic!("unknown path result"),
-        }
-    }
-    // try adding pos to the set. If it's already there, then trim this
-    // path by marking it invalid.
-    let success = map.insert(pos.clone());
-    if !success {
-        path_result = PathResult::Invalid;
+fn step(pos: (i32, i32), m: i64) -> (i32, i32) {
+    match m {
+        1 => (pos.0, pos.1 + 1),
+        2 => (pos.0, pos.1 - 1),
+        3 => (pos.0 - 1, pos.1),
+        4 => (pos.0 + 1, pos.1),
+        _ => panic!("unknown direction"),
     }
-    // unwind back to the start position
-    for m in backtrack {
-        robo.push_input(m as i64);
-        robo.start();
-        let result = robo.pop_output().unwrap();
-        if result != 1 {
-            panic!("unexpected result while backtracking");
-        }
-    }
-    return path_result;
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-fifteen/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-fifteen/input.txt").expect("couldn't load tape");
     let mut robo = intcode::IntcodeComp::new(tape);
-    let mut paths: VecDeque<Vec<u8>> = VecDeque::new();
     let mut map: HashSet<(i32, i32)> = HashSet::new();
     let mut oxygen = (0, 0);
-    paths.push_back(vec![1]);
-    paths.push_back(vec![2]);
-    paths.push_back(vec![3]);
-    paths.push_back(vec![4]);
     map.insert((0, 0));
     robo.start();
-    while !paths.is_empty() {
-        let path = paths.pop_front().unwrap();
-        let path_result = is_path_valid(&path, &mut robo, &mut map);
-        if path_result == PathResult::Invalid { continue; }
-
-        // If the path is valid, then push the path back onto the queue 4
-        // times, and for each clone add an extra direction to the path.
-        let last_move = path.last().unwrap();
-        let skip_move = match last_move {
-            1 => 2,
-            2 => 1,
-            3 => 4,
-            4 => 3,
-            _ => panic!("unknown direction"),
-        };
-        for i in 1..5 {
-            if i == skip_move { continue; }
-            let mut new_path = path.clone();
-            new_path.push(i);
-            paths.push_back(new_path);
-        }
 
-        if let PathResult::Oxygen(pos) = path_result {
-            println!("Shortest path: {}", path.len());
-            oxygen = pos.clone();
+    // Breadth-first search from the origin.  Each queue entry holds the
+    // droid's state after reaching a position, so every branch can carry on
+    // from there instead of walking the droid back and forth.
+    let mut paths: VecDeque<(usize, (i32, i32), intcode::Snapshot)> = VecDeque::new();
+    paths.push_back((0, (0, 0), robo.snapshot()));
+    while let Some((length, pos, snapshot)) = paths.pop_front() {
+        for m in 1..5 {
+            robo.restore(&snapshot);
+            robo.push_input(m);
+            robo.start();
+            let result = robo.pop_output().unwrap();
+            if result == 0 {
+                continue;
+            }
+            let next = step(pos, m);
+            // If we've already been here, a shorter path got here first.
+            if !map.insert(next) {
+                continue;
+            }
+            match result {
+                1 => (),
+                2 => {
+                    println!("Shortest path: {}", length + 1);
+                    oxygen = next;
+                },
+                _ => panic!("unknown path result"),
+            }
+            paths.push_back((length + 1, next, robo.snapshot()));
         }
     }
 
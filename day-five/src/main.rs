@@ -1,8 +1,7 @@
 #[macro_use] extern crate text_io;
 
 fn main() {
-    let line = std::fs::read_to_string("day-five/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-five/input.txt").expect("couldn't load tape");
 
     let mut comp = intcode::IntcodeComp::new(tape);
     comp.start();
@@ -1,10 +1,12 @@
 #[macro_use] extern crate text_io;
 
 fn main() {
-    let line = std::fs::read_to_string("day-nine/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-nine/input.txt").expect("couldn't load tape");
     
-    let mut comp = intcode::IntcodeComp::new(tape.clone());
+    // The BOOST tests work with numbers near the limits of an i64, so make
+    // sure nothing quietly overflows.
+    let mut comp = intcode::IntcodeComp::new(tape.clone())
+        .with_overflow_policy(intcode::OverflowPolicy::Checked);
     comp.start();
 
     while *comp.state() != intcode::IntcodeState::Finished {
@@ -1,40 +1,40 @@
-fn check(x: i64, y: i64, tape: Vec<i64>) -> bool {
-    let mut comp = intcode::IntcodeComp::new(tape);
+fn check(x: i64, y: i64, comp: &mut intcode::IntcodeComp) -> bool {
+    comp.reset();
     comp.push_input(x);
     comp.push_input(y);
     comp.start();
     comp.pop_output().unwrap() != 0
 }
 
-fn part_one(tape: &Vec<i64>) {
+fn part_one(comp: &mut intcode::IntcodeComp) {
     let mut count = 0;
     for y in 0..50 {
         for x in 0..50 {
-            if check(x, y, tape.clone()) { count += 1; }
+            if check(x, y, comp) { count += 1; }
         }
     }
     println!("Affected tiles: {}", count);
 }
 
-fn part_two(tape: &Vec<i64>) {
+fn part_two(comp: &mut intcode::IntcodeComp) {
     let mut bl = (0, 99);
     let mut ur = (99, 0);
     loop {
         // Move our window right until the bottom left
         // corner is in the beam.
-        while !check(bl.0, bl.1, tape.clone()) {
+        while !check(bl.0, bl.1, comp) {
             bl.0 += 1;
             ur.0 += 1;
         }
         // Move our window down until the upper right
         // corner is in the beam.
-        while !check(ur.0, ur.1, tape.clone()) {
+        while !check(ur.0, ur.1, comp) {
             bl.1 += 1;
             ur.1 += 1;
         }
         // If the bottom left is still in the beam, then
         // we've found our window.
-        if check(bl.0, bl.1, tape.clone()) {
+        if check(bl.0, bl.1, comp) {
             break;
         }
     }
@@ -42,8 +42,8 @@ fn part_two(tape: &Vec<i64>) {
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-nineteen/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
-    part_one(&tape);
-    part_two(&tape);
+    let tape: Vec<i64> = intcode::load_tape("day-nineteen/input.txt").expect("couldn't load tape");
+    let mut comp = intcode::IntcodeComp::new(tape);
+    part_one(&mut comp);
+    part_two(&mut comp);
 }
@@ -42,61 +42,32 @@ impl ComboGenerator {
     }
 }
 
-fn try_no_feedback(tape: Vec<i64>, phases: Vec<i64>) -> i64 {
-    let mut signal = 0;
-    for phase in phases {
-        let mut comp = intcode::IntcodeComp::new(tape.clone());
-        comp.push_input(phase);
-        comp.push_input(signal);
-        comp.start();
-        match comp.state() {
-            intcode::IntcodeState::Finished => (),
-            _ => panic!("Unexpected state: {:?}", comp.state()),
-        }
-        match comp.pop_output() {
-            Some(o) => signal = o,
-            None => panic!("Failed to get output"),
-        }
+fn run_amplifiers(tape: Vec<i64>, phases: Vec<i64>, topology: intcode::pipeline::Topology) -> i64 {
+    let mut pipeline = intcode::pipeline::Pipeline::new(tape, phases.len(), topology)
+        .with_initial_inputs(phases);
+    let report = match pipeline.run(&[0]) {
+        Ok(report) => report,
+        Err(e) => panic!("Unexpected error: {}", e),
+    };
+    if report.stop != intcode::pipeline::PipelineStop::Halted {
+        panic!("Unexpected stop: {:?}", report.stop);
+    }
+    match report.outputs.last() {
+        Some(&o) => o,
+        None => panic!("Failed to get output"),
     }
-    signal
 }
 
-fn try_feedback(tape: Vec<i64>, phases: Vec<i64>) -> i64 {
-    let mut comp_a = intcode::IntcodeComp::new(tape.clone());
-    comp_a.push_input(phases[0]);
-    let mut comp_b = intcode::IntcodeComp::new(tape.clone());
-    comp_b.push_input(phases[1]);
-    let mut comp_c = intcode::IntcodeComp::new(tape.clone());
-    comp_c.push_input(phases[2]);
-    let mut comp_d = intcode::IntcodeComp::new(tape.clone());
-    comp_d.push_input(phases[3]);
-    let mut comp_e = intcode::IntcodeComp::new(tape.clone());
-    comp_e.push_input(phases[4]);
+fn try_no_feedback(tape: Vec<i64>, phases: Vec<i64>) -> i64 {
+    run_amplifiers(tape, phases, intcode::pipeline::Topology::Linear)
+}
 
-    let mut comps = vec![comp_a, comp_b, comp_c, comp_d, comp_e];
-    let mut signal = 0;
-    let mut i = 0;
-    loop {
-        comps[i].push_input(signal);
-        comps[i].start();
-        match comps[i].pop_output() {
-            Some(o) => signal = o,
-            None => println!("Unexpected?"),
-        }
-        i += 1;
-        if i >= comps.len() {
-            if *comps[i-1].state() == intcode::IntcodeState::Finished {
-                break;
-            }
-            i = 0;
-        }
-    }
-    signal
+fn try_feedback(tape: Vec<i64>, phases: Vec<i64>) -> i64 {
+    run_amplifiers(tape, phases, intcode::pipeline::Topology::Ring)
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-seven/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-seven/input.txt").expect("couldn't load tape");
     
     let mut generator = ComboGenerator::new((0..5).collect());
     let mut largest_signal = 0;
@@ -63,13 +63,11 @@ fn connect_neighbors(start: i32, width: i32, dir: Dir, grid: &mut Grid, intersec
 }
 
 
-fn build_grid(robo: &mut intcode::IntcodeComp, grid: &mut Grid, intersections: &mut HashSet<i32>) -> (i32, i32) {
+fn build_grid(view: &str, grid: &mut Grid, intersections: &mut HashSet<i32>) -> (i32, i32) {
     let mut x: i32 = 0;
     let mut y: i32 = 0;
     let mut width: i32 = 0;
-    robo.start();
-    while let Some(output) = robo.pop_output() {
-        let char_val = (output as u8) as char;
+    for char_val in view.chars() {
         match char_val {
             '\n' => {
                 // Newline, means we need to start a new row.
@@ -146,18 +144,23 @@ fn find_proposals(directions: &str) -> Vec<(String, u8)> {
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-seventeen/input.txt").expect("file not found");
     let directions = "R,10,L,12,R,6,R,10,L,12,R,6,R,6,R,10,R,12,R,6,R,10,L,12,L,12,R,6,R,10,R,12,R,6,R,10,L,12,L,12,R,6,R,10,R,12,R,6,R,10,L,12,L,12,R,6,R,10,R,12,R,6,R,10,L,12,R,6";
     let main_routine = "C,C,A,B,A,B,A,B,A,C";
     let routine_a = "R,6,R,10,R,12,R,6";
     let routine_b = "R,10,L,12,L,12";
     let routine_c = "R,10,L,12,R,6";
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-seventeen/input.txt").expect("couldn't load tape");
     let mut robo = intcode::IntcodeComp::new(tape);
     let mut grid: Grid = HashMap::new();
     let mut intersections: HashSet<i32> = HashSet::new();
 
-    let (width, height) = build_grid(&mut robo, &mut grid, &mut intersections);
+    // The camera view ends with a blank line, and is followed by the prompt
+    // for the main routine.
+    robo.start();
+    let text = robo.read_text();
+    let split = text.find("\n\n").map_or(text.len(), |i| i + 2);
+    let (width, height) = build_grid(&text[..split], &mut grid, &mut intersections);
+    let mut output = text[split..].to_string();
 
     let mut sum = 0;
     for y in 0..height {
@@ -178,21 +181,15 @@ fn main() {
     // }
 
     for input in vec![main_routine, routine_a, routine_b, routine_c, "n"] {
-        print!("robo output: ");
-        while let Some(output) = robo.pop_output() {
-            let char_val = (output as u8) as char;
-            print!("{}", char_val);
-        }
-        for b in input.as_bytes() {
-            print!("{}", *b as char);
-            robo.push_input(*b as i64);
-        }
-        print!("{}", 10 as char);
-        robo.push_input(10);
+        print!("robo output: {}", output);
+        println!("{}", input);
+        robo.push_line(input).unwrap();
         robo.start();
+        output = robo.read_text();
     }
     println!("robo output: ");
-    while let Some(output) = robo.pop_output() {
-        println!("{}", output);
+    print!("{}", output);
+    if let Some(dust) = robo.pop_answer() {
+        println!("{}", dust);
     }
 }
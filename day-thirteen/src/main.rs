@@ -35,10 +35,8 @@ fn render_screen(x_range: &(i64, i64), y_range: &(i64, i64), tiles: &HashMap<(i6
 }
 
 fn main() {
-    let line = std::fs::read_to_string("day-thirteen/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-thirteen/input.txt").expect("couldn't load tape");
     let mut arcade = intcode::IntcodeComp::new(tape);
-    arcade.start();
 
     let mut tiles: HashMap<(i64, i64), TileId> = HashMap::new();
     let mut score = 0;
@@ -46,48 +44,62 @@ fn main() {
     let mut y_range = (0, 0);
     let mut ball_pos = (0, 0);
     let mut paddle_pos = (0, 0);
-    loop {
-        loop {
-            // Handle output to render screen.
-            let maybe_x = arcade.pop_output();
-            if maybe_x.is_none() { break; }
-            let x: i64 = maybe_x.unwrap();
-            let y: i64 = arcade.pop_output().unwrap();
-            if x == -1 && y == 0 {
-                score = arcade.pop_output().unwrap();
-                continue;
-            }
-            let tile: TileId = match arcade.pop_output().unwrap() {
-                0 => TileId::EMPTY,
-                1 => TileId::WALL,
-                2 => TileId::BLOCK,
-                3 => {
-                    paddle_pos = (x, y);
-                    TileId::PADDLE
-                },
-                4 => {
-                    ball_pos = (x, y);
-                    TileId::BALL
-                },
-                _ => panic!("Unknown tile type!"),
-            };
-            if x < x_range.0 { x_range.0 = x; }
-            if x > x_range.1 { x_range.1 = x; }
-            if y < y_range.0 { y_range.0 = y; }
-            if y > y_range.1 { y_range.1 = y; }
-            tiles.insert((x, y), tile);
+    // Output comes in groups of three, so collect them until there's a whole
+    // group to render.
+    let mut pending: Vec<i64> = Vec::new();
+    arcade.run_with(|event| {
+        match event {
+            intcode::IoEvent::NeedsInput => {
+                // Dumb input AI: just follow the ball.
+                let joystick = if paddle_pos.0 < ball_pos.0 {
+                    1
+                } else if paddle_pos.0 > ball_pos.0 {
+                    -1
+                } else {
+                    0
+                };
+                return Some(joystick);
+            },
+            intcode::IoEvent::Output(val) => pending.push(val),
         }
-
-        // Dumb input AI: just follow the ball.
-        if *arcade.state() == intcode::IntcodeState::Finished { break; }
-        if paddle_pos.0 < ball_pos.0 {
-            arcade.push_input(1);
-        } else if paddle_pos.0 > ball_pos.0 {
-            arcade.push_input(-1);
-        } else {
-            arcade.push_input(0);
+        if pending.len() < 3 {
+            return None;
         }
-        arcade.start();
+        let (x, y) = (pending[0], pending[1]);
+        let id = pending[2];
+        pending.clear();
+        if x == -1 && y == 0 {
+            score = id;
+            return None;
+        }
+        let tile: TileId = match id {
+            0 => TileId::EMPTY,
+            1 => TileId::WALL,
+            2 => TileId::BLOCK,
+            3 => {
+                paddle_pos = (x, y);
+                TileId::PADDLE
+            },
+            4 => {
+                ball_pos = (x, y);
+                TileId::BALL
+            },
+            _ => panic!("Unknown tile type!"),
+        };
+        if x < x_range.0 { x_range.0 = x; }
+        if x > x_range.1 { x_range.1 = x; }
+        if y < y_range.0 { y_range.0 = y; }
+        if y > y_range.1 { y_range.1 = y; }
+        tiles.insert((x, y), tile);
+        None
+    });
+    match arcade.state() {
+        intcode::IntcodeState::Finished => (),
+        s => panic!("Unexpected state: {:?}", s),
+    }
+    // A group cut short by the program halting will never be completed.
+    if !pending.is_empty() {
+        panic!("{}", intcode::PartialRecord { expected: 3, values: pending });
     }
     render_screen(&x_range, &y_range, &tiles);
     println!("score: {}", score);
@@ -28,49 +28,61 @@ east
     comp.start();
 
     for line in quickstart.lines() {
-        while let Some(_) = comp.pop_output() {
-            continue;
-        }
-
-        for c in line.as_bytes() {
-            comp.push_input(*c as i64);
-        }
-        comp.push_input(10);
+        comp.read_text();
+        comp.push_line(line).unwrap();
         comp.start();
     }
 
-    while let Some(output) = comp.pop_output() {
-        print!("{}", output as u8 as char);
-    }
+    print!("{}", comp.read_text());
 }
 
 fn just_play(tape: Vec<i64>) {
     let mut comp = intcode::IntcodeComp::new(tape);
-    comp.start();
-    while *comp.state() != intcode::IntcodeState::Finished {
-        while let Some(output) = comp.pop_output() {
-            print!("{}", output as u8 as char);
+    loop {
+        // Hand control back here whenever the droid wants a command, so the
+        // line can be checked and pushed with push_line().
+        comp.run_with(|event| match event {
+            intcode::IoEvent::Output(output) => {
+                print!("{}", output as u8 as char);
+                None
+            },
+            intcode::IoEvent::NeedsInput => None,
+        });
+        if *comp.state() != intcode::IntcodeState::NeedsInput {
+            break;
         }
 
         let line: String = read!("{}\r\n");
 
-        for c in line.as_bytes() {
-            comp.push_input(*c as i64);
+        // Two extra commands the droid doesn't know about, for saving the
+        // game and picking it up again later.
+        if let Some(path) = line.strip_prefix("save ") {
+            match comp.save(path) {
+                Ok(()) => println!("Saved to {}", path),
+                Err(e) => println!("Couldn't save to {}: {}", path, e),
+            }
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("load ") {
+            match intcode::IntcodeComp::load(path) {
+                Ok(saved) => {
+                    comp = saved;
+                    println!("Loaded {}", path);
+                },
+                Err(e) => println!("Couldn't load {}: {}", path, e),
+            }
+            continue;
         }
-        comp.push_input(10);
 
-        comp.start();
-    }
-    while let Some(output) = comp.pop_output() {
-        print!("{}", output as u8 as char);
+        if let Err(e) = comp.push_line(&line) {
+            println!("The droid only understands ASCII: {}", e);
+        }
     }
 }
 
 fn main() {
     let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("input.txt");
-    let line = std::fs::read_to_string(&path).expect("file not found");
-
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape(&path).expect("couldn't load tape");
     auto_win(tape.clone());
     just_play(tape.clone());
 }
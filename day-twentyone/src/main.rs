@@ -2,30 +2,24 @@ fn run_springdroid(tape: Vec<i64>, instructions: &str) {
     let mut comp = intcode::IntcodeComp::new(tape);
     comp.start();
 
-    while let Some(output) = comp.pop_output() {
-        print!("{}", output as u8 as char);
-    }
+    print!("{}", comp.read_text());
 
     print!("{}", instructions);
-    for c in instructions.as_bytes() {
-        comp.push_input(*c as i64);
+    for line in instructions.lines() {
+        comp.push_line(line).unwrap();
     }
     comp.start();
 
-    while let Some(output) = comp.pop_output() {
-        if output < 256 {
-            print!("{}", output as u8 as char);
-        } else {
-            print!("{}", output);
-        }
+    print!("{}", comp.read_text());
+    if let Some(damage) = comp.pop_answer() {
+        print!("{}", damage);
     }
     println!("\n");
 }
 
 fn main() {
     let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("input.txt");
-    let input = std::fs::read_to_string(&path).expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&input);
+    let tape: Vec<i64> = intcode::load_tape(&path).expect("couldn't load tape");
 
     let pt_one = "NOT A J
 NOT B T
@@ -1,13 +1,11 @@
 fn main() {
-    let line = std::fs::read_to_string("day-two/input.txt").expect("file not found");
-    let tape: Vec<i64> = intcode::to_tape(&line);
+    let tape: Vec<i64> = intcode::load_tape("day-two/input.txt").expect("couldn't load tape");
 
+    let mut comp = intcode::IntcodeComp::new(tape);
     for noun in 0..100 {
         for verb in 0..100 {
-            let mut tape_copy = tape.clone();
-            tape_copy[1] = noun;
-            tape_copy[2] = verb;
-            let mut comp = intcode::IntcodeComp::new(tape_copy);
+            comp.reset();
+            comp.patch(&[(1, noun), (2, verb)]).expect("couldn't patch tape");
             comp.start();
             let result = comp.get(0 as usize);
             if result == 19690720 {
@@ -0,0 +1,256 @@
+use std::io::{self, BufRead, Write};
+
+use intcode::disasm;
+use intcode::{Access, IntcodeComp, IntcodeState, WatchAction};
+
+const HELP: &str = "Commands:
+  run | r [n]                run until halt, input, breakpoint or watchpoint,
+                             or until n instructions have executed
+  step | s [n]               execute n instructions, ignoring breakpoints
+  break | b [addr]           add a breakpoint, or list breakpoints
+  delete <addr>              remove a breakpoint
+  watch <addr>[..end] [r|w|rw]
+                             pause when an instruction accesses memory
+  unwatch <id>               remove a watchpoint
+  print | p <addr> [count]   print memory
+  disasm [addr] [count]      disassemble, starting at the head by default
+  set <addr> <value>         set memory
+  input <value>...           queue input values
+  output                     print and clear queued output
+  info                       show the head, relative base and state
+  help                       show this message
+  quit | q                   exit";
+
+fn parse<T: std::str::FromStr>(arg: Option<&str>, what: &str) -> Result<T, String> {
+    match arg {
+        Some(a) => a.parse::<T>().map_err(|_| format!("invalid {}: {}", what, a)),
+        None => Err(format!("missing {}", what)),
+    }
+}
+
+fn describe(state: &IntcodeState) -> String {
+    match state {
+        IntcodeState::Ready => "ready".to_string(),
+        IntcodeState::Running => "running".to_string(),
+        IntcodeState::NeedsInput => "waiting for input".to_string(),
+        IntcodeState::Breakpoint(addr) => format!("stopped at breakpoint {}", addr),
+        IntcodeState::Watchpoint(e) => format!(
+            "stopped by watchpoint: {:?} of [{}] by instruction at {} ({} -> {})",
+            e.access, e.addr, e.ip, e.old, e.new),
+        IntcodeState::Paused => "paused after running out of instructions".to_string(),
+        IntcodeState::Finished => "finished".to_string(),
+        IntcodeState::Err(e) => format!("error: {}", e),
+    }
+}
+
+/// The most cells a single instruction covers.
+const MAX_INSTRUCTION_CELLS: usize = 4;
+
+/// The most cells the print command shows at once.
+const MAX_PRINT_COUNT: usize = 4096;
+
+/// Disassembles the line at an address, reading only the cells it could
+/// cover rather than copying the whole tape.
+fn disasm_line(comp: &mut IntcodeComp, addr: usize) -> disasm::Line {
+    let end = comp.memory().len().min(addr.saturating_add(MAX_INSTRUCTION_CELLS));
+    let window: Vec<i64> = (addr..end).map(|a| comp.get(a)).collect();
+    match disasm::disassemble_at(&window, 0) {
+        disasm::Line::Instruction { opcode, operands, .. } => disasm::Line::Instruction { addr, opcode, operands },
+        disasm::Line::Data { value, .. } => disasm::Line::Data { addr, value },
+    }
+}
+
+fn print_disasm(comp: &mut IntcodeComp, start: usize, count: usize) {
+    let mut addr = start;
+    for _ in 0..count {
+        if addr >= comp.memory().len() {
+            break;
+        }
+        let line = disasm_line(comp, addr);
+        let marker = if addr == comp.head() { "=>" } else { "  " };
+        println!("{} {}", marker, line);
+        addr += line.size();
+    }
+}
+
+fn show_stop(comp: &mut IntcodeComp) {
+    println!("{}", describe(comp.state()));
+    let head = comp.head();
+    print_disasm(comp, head, 1);
+}
+
+/// Runs a single command.  Returns false when the debugger should exit.
+fn run_command(comp: &mut IntcodeComp, line: &str) -> Result<bool, String> {
+    let mut args = line.split_whitespace();
+    let cmd = match args.next() {
+        Some(cmd) => cmd,
+        None => return Ok(true),
+    };
+    match cmd {
+        "run" | "r" => {
+            match args.next() {
+                Some(a) => comp.run_for(parse(Some(a), "count")?),
+                None => comp.start(),
+            }
+            show_stop(comp);
+        },
+        "step" | "s" => {
+            let n: usize = match args.next() {
+                Some(a) => parse(Some(a), "count")?,
+                None => 1,
+            };
+            for _ in 0..n {
+                comp.step();
+                if *comp.state() != IntcodeState::Ready {
+                    break;
+                }
+            }
+            show_stop(comp);
+        },
+        "break" | "b" => match args.next() {
+            Some(a) => comp.add_breakpoint(parse(Some(a), "address")?),
+            None => {
+                for addr in comp.breakpoints() {
+                    println!("breakpoint at {}", addr);
+                }
+            },
+        },
+        "delete" => {
+            let addr: usize = parse(args.next(), "address")?;
+            if !comp.remove_breakpoint(addr) {
+                return Err(format!("no breakpoint at {}", addr));
+            }
+        },
+        "watch" => {
+            let range = match args.next() {
+                Some(r) => r,
+                None => {
+                    for (id, range, access) in comp.watchpoints() {
+                        println!("watchpoint {}: {:?} of {}..{}", id, access, range.start, range.end);
+                    }
+                    return Ok(true);
+                },
+            };
+            let mut bounds = range.splitn(2, "..");
+            let start: usize = parse(bounds.next(), "address")?;
+            let end: usize = match bounds.next() {
+                Some(e) => parse(Some(e), "address")?,
+                None => match start.checked_add(1) {
+                    Some(end) => end,
+                    None => return Err(format!("invalid address: {}", start)),
+                },
+            };
+            let access = match args.next() {
+                Some("r") => Access::Read,
+                Some("w") => Access::Write,
+                Some("rw") | None => Access::ReadWrite,
+                Some(a) => return Err(format!("invalid access: {}", a)),
+            };
+            let id = comp.add_watchpoint(start..end, access, WatchAction::Pause);
+            println!("watchpoint {}", id);
+        },
+        "unwatch" => {
+            let id: usize = parse(args.next(), "watchpoint id")?;
+            if !comp.remove_watchpoint(id) {
+                return Err(format!("no watchpoint {}", id));
+            }
+        },
+        "print" | "p" => {
+            let start: usize = parse(args.next(), "address")?;
+            let count: usize = match args.next() {
+                Some(c) => parse(Some(c), "count")?,
+                None => 1,
+            };
+            if count > MAX_PRINT_COUNT {
+                return Err(format!("count must be at most {}", MAX_PRINT_COUNT));
+            }
+            let end = match start.checked_add(count) {
+                Some(end) => end,
+                None => return Err(format!("invalid address: {}", start)),
+            };
+            let mut row = start;
+            while row < end {
+                let row_end = row.saturating_add(8).min(end);
+                let values: Vec<String> = (row..row_end).map(|a| comp.get(a).to_string()).collect();
+                println!("{:04}: {}", row, values.join(" "));
+                row = row_end;
+            }
+        },
+        "disasm" => {
+            let start: usize = match args.next() {
+                Some(a) => parse(Some(a), "address")?,
+                None => comp.head(),
+            };
+            let count: usize = match args.next() {
+                Some(c) => parse(Some(c), "count")?,
+                None => 10,
+            };
+            print_disasm(comp, start, count);
+        },
+        "set" => {
+            let addr: usize = parse(args.next(), "address")?;
+            let value: i64 = parse(args.next(), "value")?;
+            comp.set(addr, value).map_err(|e| e.to_string())?;
+        },
+        "input" => {
+            let values = args.map(|a| parse(Some(a), "value")).collect::<Result<Vec<i64>, String>>()?;
+            if values.is_empty() {
+                return Err("missing value".to_string());
+            }
+            for v in values {
+                comp.push_input(v);
+            }
+        },
+        "output" => {
+            while let Some(o) = comp.pop_output() {
+                println!("{}", o);
+            }
+        },
+        "info" => {
+            println!("head: {}", comp.head());
+            println!("relative base: {}", comp.rel_base());
+            println!("state: {}", describe(comp.state()));
+        },
+        "help" => println!("{}", HELP),
+        "quit" | "q" => return Ok(false),
+        _ => return Err(format!("unknown command: {} (try 'help')", cmd)),
+    }
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        eprintln!("usage: intcode-dbg <tape file>");
+        std::process::exit(2);
+    }
+    let tape = match intcode::load_tape(&args[1]) {
+        Ok(tape) => tape,
+        Err(e) => {
+            eprintln!("{}: {}", args[1], e);
+            std::process::exit(2);
+        },
+    };
+    let mut comp = IntcodeComp::new(tape);
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(e) => {
+                eprintln!("couldn't read command: {}", e);
+                break;
+            },
+        }
+        match run_command(&mut comp, &line) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(msg) => println!("{}", msg),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+use std::rc::Rc;
+
+use intcode::trace::{TraceFormat, Tracer};
+use intcode::{IntcodeComp, IntcodeState};
+
+const USAGE: &str = "usage: intcode [options] <tape file> [input...]
+
+Runs a tape until it halts, starves for input, or fails.  Values after the tape
+file are queued as input before it starts.
+
+Options:
+  --input numbers|ascii    once those run out, read input from stdin, either
+                           as numbers separated by whitespace or commas, or as
+                           lines of ASCII text
+  --output numbers|text    print each output as a number on its own line (the
+                           default), or decode outputs below 128 as text
+  --max-steps <n>          stop after executing n instructions
+  --trace[=text|json]      trace every instruction to stderr
+  --dump-memory <file>     write the final memory to a file, as a text tape
+  --help                   show this message
+
+Exit codes:
+  0  the tape halted
+  1  the tape failed with an error
+  2  bad arguments, or the tape file couldn't be read
+  3  the tape needed input and none was left
+  4  the step limit was reached";
+
+const EXIT_HALTED: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_NEEDS_INPUT: i32 = 3;
+const EXIT_STEP_LIMIT: i32 = 4;
+
+#[derive(Clone, Copy, PartialEq)]
+enum InputMode {
+    None,
+    Numbers,
+    Ascii,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OutputMode {
+    Numbers,
+    Text,
+}
+
+struct Options {
+    tape: String,
+    inputs: Vec<i64>,
+    input: InputMode,
+    output: OutputMode,
+    max_steps: Option<u64>,
+    trace: Option<TraceFormat>,
+    dump_memory: Option<String>,
+}
+
+fn parse<T: std::str::FromStr>(arg: Option<String>, what: &str) -> Result<T, String> {
+    match arg {
+        Some(a) => a.parse::<T>().map_err(|_| format!("invalid {}: {}", what, a)),
+        None => Err(format!("missing {}", what)),
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        tape: String::new(),
+        inputs: Vec::new(),
+        input: InputMode::None,
+        output: OutputMode::Numbers,
+        max_steps: None,
+        trace: None,
+        dump_memory: None,
+    };
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => options.input = match args.next().as_deref() {
+                Some("numbers") => InputMode::Numbers,
+                Some("ascii") => InputMode::Ascii,
+                Some(m) => return Err(format!("invalid input mode: {}", m)),
+                None => return Err("missing input mode".to_string()),
+            },
+            "--output" => options.output = match args.next().as_deref() {
+                Some("numbers") => OutputMode::Numbers,
+                Some("text") => OutputMode::Text,
+                Some(m) => return Err(format!("invalid output mode: {}", m)),
+                None => return Err("missing output mode".to_string()),
+            },
+            "--max-steps" => options.max_steps = Some(parse(args.next(), "step count")?),
+            "--trace" | "--trace=text" => options.trace = Some(TraceFormat::Text),
+            "--trace=json" => options.trace = Some(TraceFormat::Json),
+            "--dump-memory" => options.dump_memory = Some(parse(args.next(), "file")?),
+            "--help" => {
+                println!("{}", USAGE);
+                process::exit(EXIT_HALTED);
+            },
+            // Anything else starting with "--" is a typo, but "-5" is an input.
+            a if a.starts_with("--") => return Err(format!("unknown option: {}", a)),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    options.tape = positional.next().ok_or_else(|| "missing tape file".to_string())?;
+    for arg in positional {
+        options.inputs.push(parse(Some(arg), "input")?);
+    }
+    Ok(options)
+}
+
+/// Reads input from stdin as the tape asks for it.  Whatever couldn't be read
+/// is kept in `error`, and the tape is left waiting for input.
+struct StdinInput {
+    mode: InputMode,
+    pending: VecDeque<i64>,
+    error: Rc<RefCell<Option<String>>>,
+}
+
+impl StdinInput {
+    /// Reads another line into the pending values.  Returns false at the end
+    /// of stdin, or on an error.
+    fn fill(&mut self) -> bool {
+        if self.mode == InputMode::None {
+            return false;
+        }
+        // Make sure any prompt has been seen before blocking on stdin.
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) => return false,
+            Ok(_) => (),
+            Err(e) => {
+                *self.error.borrow_mut() = Some(format!("couldn't read input: {}", e));
+                return false;
+            },
+        }
+        match self.mode {
+            InputMode::Numbers => {
+                for field in line.split(|c: char| c == ',' || c.is_whitespace()).filter(|f| !f.is_empty()) {
+                    match field.parse::<i64>() {
+                        Ok(v) => self.pending.push_back(v),
+                        Err(_) => {
+                            *self.error.borrow_mut() = Some(format!("invalid input: {}", field));
+                            return false;
+                        },
+                    }
+                }
+            },
+            InputMode::Ascii => {
+                let line = line.trim_end_matches(['\n', '\r']);
+                if let Some(ch) = line.chars().find(|c| !c.is_ascii()) {
+                    *self.error.borrow_mut() = Some(format!("non-ASCII input: {:?}", ch));
+                    return false;
+                }
+                self.pending.extend(line.bytes().map(i64::from));
+                self.pending.push_back(10);
+            },
+            InputMode::None => unreachable!(),
+        }
+        true
+    }
+
+    fn next_input(&mut self) -> Option<i64> {
+        while self.pending.is_empty() {
+            if !self.fill() {
+                return None;
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+/// Prints a single output value.
+fn print_output(out: &mut impl Write, mode: OutputMode, value: i64) -> io::Result<()> {
+    match mode {
+        OutputMode::Text if (0..128).contains(&value) => {
+            out.write_all(&[value as u8])?;
+            if value == 10 {
+                out.flush()?;
+            }
+            Ok(())
+        },
+        _ => writeln!(out, "{}", value),
+    }
+}
+
+fn dump_memory(comp: &IntcodeComp, path: &str) -> io::Result<()> {
+    let cells: Vec<String> = comp.memory().iter().map(|v| v.to_string()).collect();
+    fs::write(path, cells.join(",") + "\n")
+}
+
+fn run(options: Options) -> i32 {
+    let tape = match intcode::load_tape(&options.tape) {
+        Ok(tape) => tape,
+        Err(e) => {
+            eprintln!("{}: {}", options.tape, e);
+            return EXIT_USAGE;
+        },
+    };
+
+    let input_error = Rc::new(RefCell::new(None));
+    let mut stdin = StdinInput { mode: options.input, pending: VecDeque::new(), error: input_error.clone() };
+    let output_error = Rc::new(RefCell::new(None));
+    let sink_error = output_error.clone();
+    let output = options.output;
+    let mut stdout = io::stdout();
+    let mut comp = IntcodeComp::new(tape)
+        .with_input(move || stdin.next_input())
+        .with_output(move |v| {
+            if sink_error.borrow().is_none() {
+                if let Err(e) = print_output(&mut stdout, output, v) {
+                    *sink_error.borrow_mut() = Some(e);
+                }
+            }
+        });
+    for &v in &options.inputs {
+        comp.push_input(v);
+    }
+    if let Some(format) = options.trace {
+        comp.set_tracer(Tracer::new(BufWriter::new(io::stderr()), format));
+    }
+
+    match options.max_steps {
+        Some(n) => comp.run_for(n),
+        None => comp.start(),
+    }
+    let _ = io::stdout().flush();
+    if let Some(mut tracer) = comp.take_tracer() {
+        if let Some(e) = tracer.error() {
+            eprintln!("couldn't write trace: {}", e);
+        }
+        let _ = tracer.flush();
+    }
+    if let Some(path) = options.dump_memory {
+        if let Err(e) = dump_memory(&comp, &path) {
+            eprintln!("couldn't write memory to {}: {}", path, e);
+            return EXIT_ERROR;
+        }
+    }
+    if let Some(e) = output_error.borrow().as_ref() {
+        eprintln!("couldn't write output: {}", e);
+        return EXIT_ERROR;
+    }
+
+    match comp.state() {
+        IntcodeState::Finished => EXIT_HALTED,
+        IntcodeState::NeedsInput => match input_error.borrow().as_ref() {
+            Some(e) => {
+                eprintln!("{}", e);
+                EXIT_ERROR
+            },
+            None => {
+                eprintln!("tape needs input at {}", comp.head());
+                EXIT_NEEDS_INPUT
+            },
+        },
+        IntcodeState::Paused => {
+            eprintln!("step limit reached at {}", comp.head());
+            EXIT_STEP_LIMIT
+        },
+        IntcodeState::Err(e) => {
+            eprintln!("error: {}", e);
+            EXIT_ERROR
+        },
+        state => {
+            eprintln!("unexpected state: {:?}", state);
+            EXIT_ERROR
+        },
+    }
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(EXIT_USAGE);
+        },
+    };
+    process::exit(run(options));
+}
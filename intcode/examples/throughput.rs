@@ -0,0 +1,79 @@
+//! Times the interpreter on three workloads from the day crates, reporting
+//! the best of several runs of each:
+//!
+//!  * nine: day nine's BOOST program in sensor boost mode, one long run.
+//!  * nineteen: day nineteen's drone program on a fresh computer for every
+//!    point of a 100x100 grid.
+//!  * two: day two's program on a fresh computer for every noun and verb.
+//!
+//! Run it with `cargo run --release --example throughput` in this directory.
+//! It only uses the API the crate started with, so it can be copied into an
+//! older checkout to compare against it.
+
+extern crate intcode;
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 20;
+
+fn load(day: &str) -> Vec<i64> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(day).join("input.txt");
+    let text = fs::read_to_string(&path).expect("couldn't read tape");
+    intcode::to_tape(&text)
+}
+
+/// Runs a workload RUNS times, and prints the fastest run and its result.
+fn time<F: FnMut() -> i64>(name: &str, mut workload: F) {
+    let mut best = Duration::MAX;
+    let mut result = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = workload();
+        best = best.min(start.elapsed());
+    }
+    println!("{:<9} {:>10.2?}  (result {})", name, best, result);
+}
+
+fn main() {
+    let nine = load("day-nine");
+    time("nine", || {
+        let mut comp = intcode::IntcodeComp::new(nine.clone());
+        comp.push_input(2);
+        comp.start();
+        comp.pop_output().unwrap()
+    });
+
+    let nineteen = load("day-nineteen");
+    time("nineteen", || {
+        let mut pulled = 0;
+        for y in 0..100 {
+            for x in 0..100 {
+                let mut comp = intcode::IntcodeComp::new(nineteen.clone());
+                comp.push_input(x);
+                comp.push_input(y);
+                comp.start();
+                pulled += comp.pop_output().unwrap();
+            }
+        }
+        pulled
+    });
+
+    let two = load("day-two");
+    time("two", || {
+        for noun in 0..100 {
+            for verb in 0..100 {
+                let mut tape = two.clone();
+                tape[1] = noun;
+                tape[2] = verb;
+                let mut comp = intcode::IntcodeComp::new(tape);
+                comp.start();
+                if comp.get(0) == 19690720 {
+                    return 100 * noun + verb;
+                }
+            }
+        }
+        -1
+    });
+}
@@ -0,0 +1,102 @@
+//! Helpers for tapes that talk in ASCII text.
+//!
+//! Text is passed one character per value, with lines ending in a newline
+//! (10).  Tapes like this often report their final answer as a single value
+//! too large to be a character, so output is split into text (values 0 to
+//! 127) and answers (everything else).
+
+use std::error;
+use std::fmt;
+
+use super::{IntcodeComp, Word};
+
+/// Input that can't be passed to a computer as ASCII text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NotAscii {
+    /// The (0-based) position of the offending character, counted in
+    /// characters.
+    pub position: usize,
+    /// The offending character.
+    pub ch: char,
+}
+
+impl fmt::Display for NotAscii {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "non-ASCII character {:?} at position {}", self.ch, self.position)
+    }
+}
+
+impl error::Error for NotAscii {}
+
+/// Returns the character an output value stands for, or None if it's an
+/// answer rather than text.
+fn to_char<W: Word>(value: &W) -> Option<char> {
+    match value.to_i64() {
+        Some(v) if (0..128).contains(&v) => Some(v as u8 as char),
+        _ => None,
+    }
+}
+
+impl<W: Word> IntcodeComp<W> {
+    /// Pushes a line of text into the computer's input queue, followed by a
+    /// newline.  If the line has any non-ASCII characters, nothing is pushed
+    /// and the first of them is reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // This tape echoes its input.
+    /// let tape: Vec<i64> = vec![3,7,4,7,1105,1,0,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.push_line("hi").unwrap();
+    /// comp.start();
+    /// assert_eq!(comp.read_text(), "hi\n");
+    ///
+    /// let err = comp.push_line("naïve").unwrap_err();
+    /// assert_eq!(err.position, 2);
+    /// assert_eq!(err.to_string(), "non-ASCII character 'ï' at position 2");
+    /// ```
+    pub fn push_line(&mut self, line: &str) -> Result<(), NotAscii> {
+        if let Some((position, ch)) = line.chars().enumerate().find(|&(_, c)| !c.is_ascii()) {
+            return Err(NotAscii { position, ch });
+        }
+        for b in line.bytes() {
+            self.push_input(W::from_i64(b as i64));
+        }
+        self.push_input(W::from_i64(10));
+        Ok(())
+    }
+
+    /// Pops text from the front of the computer's output queue.  This stops
+    /// at the first answer, leaving it in the queue for pop_answer().
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // This tape prints "OK" then gives an answer of 1000.
+    /// let tape: Vec<i64> = vec![104,79,104,75,104,10,104,1000,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.start();
+    /// assert_eq!(comp.pop_answer(), None);
+    /// assert_eq!(comp.read_text(), "OK\n");
+    /// assert_eq!(comp.read_text(), "");
+    /// assert_eq!(comp.pop_answer(), Some(1000));
+    /// ```
+    pub fn read_text(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(ch) = self.outputs.front().and_then(to_char) {
+            text.push(ch);
+            self.outputs.pop_front();
+        }
+        text
+    }
+
+    /// Pops the value at the front of the computer's output queue, but only
+    /// if it's an answer rather than text.  See read_text() for an example.
+    pub fn pop_answer(&mut self) -> Option<W> {
+        match self.outputs.front() {
+            Some(value) if to_char(value).is_none() => self.outputs.pop_front(),
+            _ => None,
+        }
+    }
+}
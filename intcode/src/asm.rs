@@ -0,0 +1,396 @@
+//! Builds a tape from a small, line-oriented assembly language.
+//!
+//! The syntax is the same one produced by the disasm module, so a listing can
+//! be edited by hand and assembled again.  Each line holds, in order:
+//!  * An optional address, like `0004:`.  These are what disasm prints in
+//!    front of every line, and they're ignored.
+//!  * Any number of labels, like `loop:`.  A label names the position of
+//!    whatever comes next on the tape.
+//!  * An optional instruction or `DATA` directive.
+//!  * An optional comment, starting with `;`.
+//!
+//! Instructions are written as a mnemonic (ADD, MUL, IN, OUT, JT, JF, LT, EQ,
+//! ARB or HLT, in any case) followed by comma-separated parameters.  Each
+//! parameter's mode is given by its form:
+//!  * Position - `[addr]`
+//!  * Immediate - `#value`
+//!  * Relative - `rb[offset]`, `rb[+offset]` or `rb[-offset]`
+//!
+//! A `DATA` directive places its comma-separated values directly on the tape.
+//! Anywhere a value is expected, a label can be used too, optionally with an
+//! offset added or subtracted (e.g. `[buf+1]`).
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use super::{Instruction, Mode, Opcode};
+
+/// The ways a program can fail to assemble.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AsmErrorKind {
+    /// The name in the instruction position isn't a known mnemonic.
+    UnknownMnemonic(String),
+    /// The instruction was given the wrong number of parameters.
+    OperandCount { expected: usize, found: usize },
+    /// An output parameter was given in immediate mode.
+    ImmediateWrite,
+    /// The text couldn't be parsed.  A description of what was expected is
+    /// included.
+    Syntax(String),
+    /// A number doesn't fit in a tape cell.
+    NumberOutOfRange,
+    /// A label was used but never defined.
+    UndefinedLabel(String),
+    /// A label was defined more than once.
+    DuplicateLabel(String),
+}
+
+/// An error produced while assembling, along with the (1-based) line and
+/// column where it was found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            AsmErrorKind::UnknownMnemonic(ref name) => write!(f, "unknown mnemonic '{}'", name),
+            AsmErrorKind::OperandCount { expected, found } =>
+                write!(f, "expected {} parameters, found {}", expected, found),
+            AsmErrorKind::ImmediateWrite => write!(f, "output parameters can't be immediate"),
+            AsmErrorKind::Syntax(ref expected) => write!(f, "expected {}", expected),
+            AsmErrorKind::NumberOutOfRange => write!(f, "number out of range"),
+            AsmErrorKind::UndefinedLabel(ref name) => write!(f, "undefined label '{}'", name),
+            AsmErrorKind::DuplicateLabel(ref name) => write!(f, "label '{}' is already defined", name),
+        }
+    }
+}
+
+impl error::Error for AsmError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Punct(char),
+}
+
+/// A token along with the column it started at.
+type Spanned = (usize, Token);
+
+/// A value that may refer to labels.  Each term is added or subtracted, and
+/// carries its column so undefined labels can be reported precisely.
+#[derive(Debug)]
+struct Expr {
+    terms: Vec<(i128, usize, Term)>,
+}
+
+#[derive(Debug)]
+enum Term {
+    Number(u64),
+    Label(String),
+}
+
+/// A cell waiting for its labels to be resolved.
+#[derive(Debug)]
+enum Cell {
+    Fixed(i64),
+    Expr(Expr),
+}
+
+fn tokenize(line: usize, text: &str) -> Result<Vec<Spanned>, AsmError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c == ';' {
+            break;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            match digits.parse::<u64>() {
+                Ok(n) => tokens.push((column, Token::Number(n))),
+                Err(_) => return Err(AsmError { line, column, kind: AsmErrorKind::NumberOutOfRange }),
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((column, Token::Ident(chars[start..i].iter().collect())));
+        } else if ":,[]#+-".contains(c) {
+            tokens.push((column, Token::Punct(c)));
+            i += 1;
+        } else {
+            return Err(AsmError {
+                line,
+                column,
+                kind: AsmErrorKind::Syntax(format!("a mnemonic, label or parameter, not '{}'", c)),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Walks the tokens of a single line.
+struct Parser {
+    line: usize,
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// The column just past the end of the line, for errors at the end.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.1)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|t| &t.1)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map(|t| t.0).unwrap_or(self.end)
+    }
+
+    fn error(&self, kind: AsmErrorKind) -> AsmError {
+        AsmError { line: self.line, column: self.column(), kind }
+    }
+
+    fn syntax(&self, expected: &str) -> AsmError {
+        self.error(AsmErrorKind::Syntax(expected.to_string()))
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).map(|t| t.1.clone());
+        self.pos += 1;
+        t
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), AsmError> {
+        if self.eat(c) {
+            return Ok(());
+        }
+        Err(self.syntax(&format!("'{}'", c)))
+    }
+
+    fn expr(&mut self) -> Result<Expr, AsmError> {
+        let mut terms = Vec::new();
+        let mut sign = 1;
+        if self.eat('-') {
+            sign = -1;
+        } else {
+            self.eat('+');
+        }
+        loop {
+            let column = self.column();
+            let term = match self.next() {
+                Some(Token::Number(n)) => Term::Number(n),
+                Some(Token::Ident(name)) => Term::Label(name),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.syntax("a number or label"));
+                },
+            };
+            terms.push((sign, column, term));
+            if self.eat('+') {
+                sign = 1;
+            } else if self.eat('-') {
+                sign = -1;
+            } else {
+                return Ok(Expr { terms });
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<(Mode, Expr), AsmError> {
+        if self.eat('#') {
+            return Ok((Mode::Immediate, self.expr()?));
+        }
+        let mode = if self.peek() == Some(&Token::Ident("rb".to_string()))
+                && self.peek_at(1) == Some(&Token::Punct('[')) {
+            self.pos += 1;
+            Mode::Relative
+        } else {
+            Mode::Position
+        };
+        if !self.eat('[') {
+            return Err(self.syntax("a parameter like [addr], #value or rb[offset]"));
+        }
+        let e = self.expr()?;
+        self.expect(']')?;
+        Ok((mode, e))
+    }
+
+    /// Parses a comma-separated list, stopping at the end of the line.
+    fn list<T, F>(&mut self, mut item: F) -> Result<Vec<T>, AsmError>
+            where F: FnMut(&mut Parser) -> Result<T, AsmError> {
+        let mut items = Vec::new();
+        if self.peek().is_none() {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if self.peek().is_none() {
+                return Ok(items);
+            }
+            self.expect(',')?;
+        }
+    }
+}
+
+/// Assembles a program into a tape, ready to be passed to IntcodeComp::new().
+/// 
+/// # Example
+/// 
+/// ```
+/// let src = "
+///         IN [count]          ; how many times to loop
+/// loop:   OUT [count]
+///         ADD [count], #-1, [count]
+///         JT [count], #loop
+///         HLT
+/// count:  DATA 0
+/// ";
+/// let tape = intcode::asm::assemble(src).unwrap();
+/// assert_eq!(tape, vec![3,12,4,12,1001,12,-1,12,1005,12,2,99,0]);
+/// 
+/// let mut comp = intcode::IntcodeComp::new(tape);
+/// comp.push_input(3);
+/// comp.start();
+/// assert_eq!(comp.pop_output(), Some(3));
+/// assert_eq!(comp.pop_output(), Some(2));
+/// assert_eq!(comp.pop_output(), Some(1));
+/// assert_eq!(comp.pop_output(), None);
+/// ```
+/// 
+/// Disassembled listings assemble back to the original tape:
+/// 
+/// ```
+/// let tape: Vec<i64> = vec![109,19,204,-34,1001,3,1,3,1108,1,2,5,99,-7];
+/// let listing: Vec<String> = intcode::disasm::disassemble(&tape)
+///     .iter()
+///     .map(|l| l.to_string())
+///     .collect();
+/// assert_eq!(intcode::asm::assemble(&listing.join("\n")), Ok(tape));
+/// ```
+/// 
+/// Errors report where the problem was found:
+/// 
+/// ```
+/// let err = intcode::asm::assemble("HLT\nADD [0], #1, #2").unwrap_err();
+/// assert_eq!(err.to_string(), "line 2, column 1: output parameters can't be immediate");
+/// ```
+pub fn assemble(src: &str) -> Result<Vec<i64>, AsmError> {
+    let mut cells: Vec<(usize, Cell)> = Vec::new();
+    let mut labels: HashMap<String, usize> = HashMap::new();
+
+    for (i, text) in src.lines().enumerate() {
+        let line = i + 1;
+        let tokens = tokenize(line, text)?;
+        let end = text.chars().count() + 1;
+        let mut p = Parser { line, tokens, pos: 0, end };
+
+        // An address left over from a listing.
+        if let (Some(Token::Number(_)), Some(Token::Punct(':'))) = (p.peek(), p.peek_at(1)) {
+            p.pos += 2;
+        }
+
+        // Labels.
+        while let (Some(Token::Ident(name)), Some(Token::Punct(':'))) = (p.peek(), p.peek_at(1)) {
+            if labels.insert(name.clone(), cells.len()).is_some() {
+                return Err(p.error(AsmErrorKind::DuplicateLabel(name.clone())));
+            }
+            p.pos += 2;
+        }
+
+        // Instruction or directive.
+        let column = p.column();
+        let name = match p.next() {
+            None => continue,
+            Some(Token::Ident(name)) => name,
+            Some(_) => {
+                p.pos -= 1;
+                return Err(p.syntax("a mnemonic or label"));
+            },
+        };
+        if name.eq_ignore_ascii_case("data") {
+            let values = p.list(|p| p.expr())?;
+            if values.is_empty() {
+                return Err(p.syntax("at least one value"));
+            }
+            cells.extend(values.into_iter().map(|v| (line, Cell::Expr(v))));
+            continue;
+        }
+        let opcode = match Opcode::from_mnemonic(&name) {
+            Some(op) => op,
+            None => return Err(AsmError { line, column, kind: AsmErrorKind::UnknownMnemonic(name) }),
+        };
+        let operands = p.list(|p| p.operand())?;
+        if operands.len() != opcode.num_params() {
+            return Err(AsmError {
+                line,
+                column,
+                kind: AsmErrorKind::OperandCount { expected: opcode.num_params(), found: operands.len() },
+            });
+        }
+        let mut inst = Instruction { opcode, modes: [Mode::Position; 3] };
+        for (i, &(mode, _)) in operands.iter().enumerate() {
+            inst.modes[i] = mode;
+        }
+        if Instruction::decode(inst.encode()) != Ok(inst) {
+            return Err(AsmError { line, column, kind: AsmErrorKind::ImmediateWrite });
+        }
+        cells.push((line, Cell::Fixed(inst.encode())));
+        cells.extend(operands.into_iter().map(|(_, e)| (line, Cell::Expr(e))));
+    }
+
+    cells.into_iter().map(|(line, cell)| match cell {
+        Cell::Fixed(v) => Ok(v),
+        Cell::Expr(e) => resolve(line, &e, &labels),
+    }).collect()
+}
+
+/// Computes the value of an expression once all labels are known.
+fn resolve(line: usize, e: &Expr, labels: &HashMap<String, usize>) -> Result<i64, AsmError> {
+    let mut total: i128 = 0;
+    for &(sign, column, ref term) in &e.terms {
+        let value = match *term {
+            Term::Number(n) => n as i128,
+            Term::Label(ref name) => match labels.get(name) {
+                Some(&addr) => addr as i128,
+                None => return Err(AsmError {
+                    line,
+                    column,
+                    kind: AsmErrorKind::UndefinedLabel(name.clone()),
+                }),
+            },
+        };
+        total += sign * value;
+        if total < i64::MIN as i128 || total > i64::MAX as i128 {
+            return Err(AsmError { line, column, kind: AsmErrorKind::NumberOutOfRange });
+        }
+    }
+    Ok(total as i64)
+}
@@ -0,0 +1,353 @@
+//! A compact binary format for tapes.
+//!
+//! A binary tape is laid out as:
+//!  * The magic bytes `ICTP`.
+//!  * A version byte, currently 1.
+//!  * A word size byte: the width in bytes of the cells the tape was written
+//!    from (8 for i64, 16 for i128).
+//!  * The number of cells, as a varint.
+//!  * Each cell, zigzag encoded and then written as a varint.
+//!  * A CRC-32 of everything before it, as 4 little-endian bytes.
+//!
+//! Varints are LEB128: seven bits per byte, least significant group first,
+//! with the top bit set on every byte but the last.  Zigzag encoding maps
+//! small negative numbers to small unsigned ones (0, -1, 1, -2, ... become 0,
+//! 1, 2, 3, ...), so typical tapes take one or two bytes per cell.
+//!
+//! BinaryTape checks a buffer and then reads cells straight out of it, without
+//! copying.  load_tape() recognises binary files by their magic bytes, so
+//! anything that loads a tape accepts either format.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use super::Word;
+
+/// The bytes every binary tape starts with.
+pub const MAGIC: [u8; 4] = *b"ICTP";
+
+/// The version written by encode(), and the only one BinaryTape accepts.
+pub const VERSION: u8 = 1;
+
+/// The ways a binary tape can fail to read.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BinaryTapeError {
+    /// The buffer doesn't start with MAGIC.
+    BadMagic,
+    /// The version byte isn't one this crate understands.  It's included.
+    UnsupportedVersion(u8),
+    /// The word size isn't 8 or 16.  It's included.
+    UnsupportedWordSize(u8),
+    /// The buffer ends partway through the tape.
+    Truncated,
+    /// The checksum doesn't match the contents, so the buffer is corrupt.
+    BadChecksum { expected: u32, found: u32 },
+    /// A cell is malformed, or doesn't fit in the word type it's being read
+    /// into.  Its (0-based) index is included.
+    BadCell(usize),
+    /// There are bytes between the last cell and the checksum.
+    TrailingData,
+}
+
+impl fmt::Display for BinaryTapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BinaryTapeError::BadMagic => write!(f, "not a binary tape"),
+            BinaryTapeError::UnsupportedVersion(v) => write!(f, "unsupported binary tape version {}", v),
+            BinaryTapeError::UnsupportedWordSize(s) => write!(f, "unsupported word size {}", s),
+            BinaryTapeError::Truncated => write!(f, "binary tape is truncated"),
+            BinaryTapeError::BadChecksum { expected, found } =>
+                write!(f, "checksum mismatch (expected {:08x}, found {:08x})", expected, found),
+            BinaryTapeError::BadCell(i) => write!(f, "cell {} is malformed or out of range", i),
+            BinaryTapeError::TrailingData => write!(f, "unexpected data after the last cell"),
+        }
+    }
+}
+
+impl error::Error for BinaryTapeError {}
+
+/// A word type that can be written to and read from a binary tape.
+pub trait BinaryWord: Word + Copy {
+    /// The word size recorded in the header.
+    const WORD_SIZE: u8;
+
+    /// Widens the word for encoding.
+    fn to_i128(self) -> i128;
+
+    /// Narrows a decoded cell, if it fits.
+    fn from_i128(value: i128) -> Option<Self>;
+}
+
+impl BinaryWord for i64 {
+    const WORD_SIZE: u8 = 8;
+
+    fn to_i128(self) -> i128 {
+        i128::from(self)
+    }
+
+    fn from_i128(value: i128) -> Option<i64> {
+        i64::try_from(value).ok()
+    }
+}
+
+impl BinaryWord for i128 {
+    const WORD_SIZE: u8 = 16;
+
+    fn to_i128(self) -> i128 {
+        self
+    }
+
+    fn from_i128(value: i128) -> Option<i128> {
+        Some(value)
+    }
+}
+
+/// Computes the CRC-32 (as used by zip and PNG) of some bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u128) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads a varint from the front of a buffer, returning it and the number of
+/// bytes it took up.  Only the encoding write_varint() produces is accepted:
+/// a value too big for a u128, or one with a zero final byte after the first,
+/// is malformed.
+fn read_varint(bytes: &[u8]) -> Option<(u128, usize)> {
+    let mut value = 0u128;
+    for (i, &b) in bytes.iter().enumerate() {
+        let shift = i * 7;
+        // The byte at shift 126 is the last one there's room for, and only
+        // its low two bits fit.
+        if shift >= 128 || (shift == 126 && b > 0x03) {
+            return None;
+        }
+        value |= u128::from(b & 0x7f) << shift;
+        if b & 0x80 == 0 {
+            if b == 0 && i > 0 {
+                return None;
+            }
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn zigzag(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+fn unzigzag(value: u128) -> i128 {
+    ((value >> 1) as i128) ^ -((value & 1) as i128)
+}
+
+/// Encodes a tape in the binary format.
+///
+/// # Example
+///
+/// ```
+/// use intcode::binary::{self, BinaryTape};
+///
+/// let tape: Vec<i64> = vec![109,-1,204,1,99];
+/// let bytes = binary::encode(&tape);
+/// assert_eq!(&bytes[..4], b"ICTP");
+/// assert_eq!(binary::decode::<i64>(&bytes).unwrap(), tape);
+///
+/// let view = BinaryTape::new(&bytes).unwrap();
+/// assert_eq!(view.len(), 5);
+/// assert_eq!(view.cells().nth(1), Some(-1));
+///
+/// let mut corrupt = bytes.clone();
+/// corrupt[7] ^= 1;
+/// assert!(matches!(BinaryTape::new(&corrupt),
+///                  Err(binary::BinaryTapeError::BadChecksum { .. })));
+/// ```
+pub fn encode<W: BinaryWord>(tape: &[W]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(tape.len() * 2 + 16);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.push(W::WORD_SIZE);
+    write_varint(&mut out, tape.len() as u128);
+    for &cell in tape {
+        write_varint(&mut out, zigzag(cell.to_i128()));
+    }
+    let crc = crc32(&out);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out
+}
+
+/// Checks and decodes a binary tape into a Vec of words.
+pub fn decode<W: BinaryWord>(bytes: &[u8]) -> Result<Vec<W>, BinaryTapeError> {
+    BinaryTape::new(bytes)?.to_vec()
+}
+
+/// A checked binary tape, borrowed from the buffer it was read from.
+#[derive(Clone, Copy, Debug)]
+pub struct BinaryTape<'a> {
+    word_size: u8,
+    len: usize,
+    /// The encoded cells, without the header or checksum.
+    cells: &'a [u8],
+}
+
+impl<'a> BinaryTape<'a> {
+    /// Checks a buffer's header, checksum and cells.  Nothing is copied; the
+    /// cells are decoded again each time they're read.
+    ///
+    /// # Example
+    ///
+    /// A cell whose varint doesn't fit in 128 bits, or that has more bytes
+    /// than it needs, is malformed.
+    ///
+    /// ```
+    /// use intcode::binary::{BinaryTape, BinaryTapeError};
+    ///
+    /// // Builds an i128 tape holding a single cell with the given bytes.
+    /// fn tape(cell: &[u8]) -> Vec<u8> {
+    ///     let mut bytes = b"ICTP\x01\x10\x01".to_vec();
+    ///     bytes.extend_from_slice(cell);
+    /// #   let mut crc = !0u32;
+    /// #   for &b in &bytes {
+    /// #       crc ^= u32::from(b);
+    /// #       for _ in 0..8 {
+    /// #           crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+    /// #       }
+    /// #   }
+    /// #   let crc = !crc;
+    ///     bytes.extend_from_slice(&crc.to_le_bytes());
+    ///     bytes
+    /// }
+    ///
+    /// let mut widest = vec![0xff; 18];
+    /// widest.push(0x03);
+    /// assert_eq!(BinaryTape::new(&tape(&widest)).unwrap().cells().next(), Some(i128::MIN));
+    /// widest[18] = 0x04;
+    /// assert_eq!(BinaryTape::new(&tape(&widest)).unwrap_err(), BinaryTapeError::BadCell(0));
+    ///
+    /// assert_eq!(BinaryTape::new(&tape(&[0x01])).unwrap().cells().next(), Some(-1));
+    /// assert_eq!(BinaryTape::new(&tape(&[0x81, 0x00])).unwrap_err(), BinaryTapeError::BadCell(0));
+    /// assert_eq!(BinaryTape::new(&tape(&[0x80, 0x80, 0x00])).unwrap_err(), BinaryTapeError::BadCell(0));
+    /// ```
+    pub fn new(bytes: &'a [u8]) -> Result<BinaryTape<'a>, BinaryTapeError> {
+        if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+            return Err(BinaryTapeError::BadMagic);
+        }
+        if bytes.len() < MAGIC.len() + 2 + 4 {
+            return Err(BinaryTapeError::Truncated);
+        }
+        let (body, crc) = bytes.split_at(bytes.len() - 4);
+        let expected = u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]);
+        let found = crc32(body);
+        if expected != found {
+            return Err(BinaryTapeError::BadChecksum { expected, found });
+        }
+        let version = body[MAGIC.len()];
+        if version != VERSION {
+            return Err(BinaryTapeError::UnsupportedVersion(version));
+        }
+        let word_size = body[MAGIC.len() + 1];
+        if word_size != 8 && word_size != 16 {
+            return Err(BinaryTapeError::UnsupportedWordSize(word_size));
+        }
+        let rest = &body[MAGIC.len() + 2..];
+        let (len, used) = read_varint(rest).ok_or(BinaryTapeError::Truncated)?;
+        let len = usize::try_from(len).map_err(|_| BinaryTapeError::Truncated)?;
+        let tape = BinaryTape { word_size, len, cells: &rest[used..] };
+
+        // Walk the cells once, so reading them later can't fail.
+        let mut pos = 0;
+        for i in 0..len {
+            let (value, used) = match read_varint(&tape.cells[pos..]) {
+                Some(v) => v,
+                None if pos == tape.cells.len() => return Err(BinaryTapeError::Truncated),
+                None => return Err(BinaryTapeError::BadCell(i)),
+            };
+            if word_size == 8 && i64::try_from(unzigzag(value)).is_err() {
+                return Err(BinaryTapeError::BadCell(i));
+            }
+            pos += used;
+        }
+        if pos != tape.cells.len() {
+            return Err(BinaryTapeError::TrailingData);
+        }
+        Ok(tape)
+    }
+
+    /// Returns the word size recorded in the header.
+    pub fn word_size(&self) -> u8 {
+        self.word_size
+    }
+
+    /// Returns the number of cells.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tape has no cells.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator that decodes each cell in turn.
+    pub fn cells(&self) -> Cells<'a> {
+        Cells { bytes: self.cells }
+    }
+
+    /// Decodes every cell into a Vec of words.  Fails if a cell doesn't fit
+    /// in the word type.
+    pub fn to_vec<W: BinaryWord>(&self) -> Result<Vec<W>, BinaryTapeError> {
+        let mut tape = Vec::with_capacity(self.len);
+        for (i, cell) in self.cells().enumerate() {
+            tape.push(W::from_i128(cell).ok_or(BinaryTapeError::BadCell(i))?);
+        }
+        Ok(tape)
+    }
+}
+
+/// Decodes the cells of a BinaryTape, see BinaryTape::cells().
+#[derive(Clone, Debug)]
+pub struct Cells<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Cells<'a> {
+    type Item = i128;
+
+    fn next(&mut self) -> Option<i128> {
+        let (value, used) = read_varint(self.bytes)?;
+        self.bytes = &self.bytes[used..];
+        Some(unzigzag(value))
+    }
+}
+
+/// Converts a text tape (see the tape module) to the binary format.
+///
+/// # Example
+///
+/// ```
+/// let bytes = intcode::binary::text_to_binary("1,9,10,3,\n2,3,11,0,99\n").unwrap();
+/// assert_eq!(intcode::binary::binary_to_text(&bytes).unwrap(), "1,9,10,3,2,3,11,0,99\n");
+/// ```
+pub fn text_to_binary(text: &str) -> Result<Vec<u8>, super::TapeParseError> {
+    Ok(encode(&super::parse_tape(text)?))
+}
+
+/// Converts a binary tape to comma-separated text, on a single line.
+pub fn binary_to_text(bytes: &[u8]) -> Result<String, BinaryTapeError> {
+    let cells: Vec<String> = BinaryTape::new(bytes)?.cells().map(|c| c.to_string()).collect();
+    Ok(cells.join(",") + "\n")
+}
@@ -0,0 +1,136 @@
+//! Turns a tape back into a readable listing of instructions.
+//!
+//! Each cell is decoded with the same Instruction::decode() the computer uses
+//! when executing, so a listing always matches what the computer would do if
+//! its head landed on that cell.  Parameters are rendered according to their
+//! mode:
+//!  * Position - `[addr]`
+//!  * Immediate - `#value`
+//!  * Relative - `rb[+offset]` or `rb[-offset]`
+//!
+//! Cells that can't be decoded as an instruction (or whose instruction would
+//! run off the end of the tape) are rendered as a `DATA` directive instead.
+
+use std::fmt;
+
+use super::{Instruction, Mode, Opcode};
+
+/// A single decoded parameter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Operand {
+    pub mode: Mode,
+    pub value: i64,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            Mode::Position => write!(f, "[{}]", self.value),
+            Mode::Immediate => write!(f, "#{}", self.value),
+            Mode::Relative => write!(f, "rb[{:+}]", self.value),
+        }
+    }
+}
+
+/// A single line of a listing: either an instruction along with its
+/// parameters, or a raw data cell.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Line {
+    Instruction { addr: usize, opcode: Opcode, operands: Vec<Operand> },
+    Data { addr: usize, value: i64 },
+}
+
+impl Line {
+    /// Returns the position on the tape where this line starts.
+    pub fn addr(&self) -> usize {
+        match *self {
+            Line::Instruction { addr, .. } => addr,
+            Line::Data { addr, .. } => addr,
+        }
+    }
+
+    /// Returns the number of cells this line covers.
+    pub fn size(&self) -> usize {
+        match *self {
+            Line::Instruction { ref operands, .. } => 1 + operands.len(),
+            Line::Data { .. } => 1,
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}: ", self.addr())?;
+        match *self {
+            Line::Instruction { opcode, ref operands, .. } => {
+                write!(f, "{}", opcode.mnemonic())?;
+                for (i, operand) in operands.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", sep, operand)?;
+                }
+                Ok(())
+            },
+            Line::Data { value, .. } => write!(f, "DATA {}", value),
+        }
+    }
+}
+
+/// Decodes the single line starting at the given position on the tape.  A
+/// value is only treated as an instruction if it's in the canonical form that
+/// Instruction::encode() would produce, and all of its parameters fit on the
+/// tape; otherwise it's treated as data.
+/// 
+/// # Example
+/// 
+/// ```
+/// let tape: Vec<i64> = vec![1,5,6,4,0,49,50];
+/// let line = intcode::disasm::disassemble_at(&tape, 0);
+/// assert_eq!(line.to_string(), "0000: ADD [5], [6], [4]");
+/// assert_eq!(line.size(), 4);
+/// let line = intcode::disasm::disassemble_at(&tape, 5);
+/// assert_eq!(line.to_string(), "0005: DATA 49");
+/// ```
+pub fn disassemble_at(tape: &[i64], addr: usize) -> Line {
+    let value = tape.get(addr).cloned().unwrap_or(0);
+    let data = Line::Data { addr, value };
+    let inst = match Instruction::decode(value) {
+        Ok(inst) => inst,
+        Err(_) => return data,
+    };
+    if inst.encode() != value || addr + inst.size() > tape.len() {
+        return data;
+    }
+    let operands = (0..inst.opcode.num_params())
+        .map(|i| Operand { mode: inst.modes[i], value: tape[addr + 1 + i] })
+        .collect();
+    Line::Instruction { addr, opcode: inst.opcode, operands }
+}
+
+/// Walks the whole tape from the start, decoding one line at a time.
+/// 
+/// # Example
+/// 
+/// ```
+/// let tape: Vec<i64> = vec![109,-3,203,1,204,-1,99,7];
+/// let listing: Vec<String> = intcode::disasm::disassemble(&tape)
+///     .iter()
+///     .map(|l| l.to_string())
+///     .collect();
+/// assert_eq!(listing, vec![
+///     "0000: ARB #-3",
+///     "0002: IN rb[+1]",
+///     "0004: OUT rb[-1]",
+///     "0006: HLT",
+///     "0007: DATA 7",
+/// ]);
+/// ```
+pub fn disassemble(tape: &[i64]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < tape.len() {
+        let line = disassemble_at(tape, addr);
+        addr += line.size();
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,252 @@
+//! The interpreter's fast path.
+//!
+//! Most computers never have a tracer, watchpoints or breakpoints attached,
+//! and keep their memory dense.  For those, run() hands over to run_fast(),
+//! which executes straight out of the tape's Vec: it doesn't match on the
+//! memory kind for every access, or check for a tracer or watchpoints for
+//! every parameter, and it keeps the head and relative base in locals until
+//! the computer stops.  Its behaviour is otherwise identical to the general
+//! path in execute_one() and the instruction helpers, which remain the
+//! reference for what each instruction does.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::mem;
+
+use super::{DecodeError, Instruction, IntcodeComp, IntcodeState, Mode, Opcode, OverflowPolicy, Program, Word};
+use super::{DECODE_CACHE_AFTER, DECODE_CACHE_CELLS};
+use super::memory::Memory;
+
+/// Why the fast path stopped with an error, before the details are filled
+/// in from the computer.
+enum Fault<W> {
+    Decode(DecodeError),
+    Address(W),
+    Overflow(W, W),
+}
+
+/// Returns the value at an address, or zero past the end of the tape.
+#[inline(always)]
+fn load<W: Word>(cells: &[W], i: usize) -> W {
+    match cells.get(i) {
+        Some(v) => v.clone(),
+        None => W::from_i64(0),
+    }
+}
+
+/// Converts a computed address into a tape index, see check_address().
+#[inline(always)]
+fn address<W: Word>(pos: W, max_memory: usize) -> Result<usize, Fault<W>> {
+    match pos.to_i64() {
+        Some(i) if i >= 0 && (i as u64) < max_memory as u64 => Ok(i as usize),
+        _ => Err(Fault::Address(pos)),
+    }
+}
+
+/// Returns the address an output parameter at the given position refers to.
+#[inline(always)]
+fn target<W: Word>(cells: &[W], at: usize, mode: Mode, rel_base: i64, max_memory: usize)
+        -> Result<usize, Fault<W>> {
+    let raw = load(cells, at);
+    match mode {
+        Mode::Relative => match W::from_i64(rel_base).checked_add(&raw) {
+            Some(pos) => address(pos, max_memory),
+            // See get_param().
+            None => Err(Fault::Address(raw)),
+        },
+        // Instruction::decode() rejects immediate output parameters.
+        _ => address(raw, max_memory),
+    }
+}
+
+/// Returns the value of an input parameter at the given position.
+#[inline(always)]
+fn param<W: Word>(cells: &[W], at: usize, mode: Mode, rel_base: i64, max_memory: usize)
+        -> Result<W, Fault<W>> {
+    match mode {
+        Mode::Immediate => Ok(load(cells, at)),
+        _ => Ok(load(cells, target(cells, at, mode, rel_base, max_memory)?)),
+    }
+}
+
+/// Writes a value, growing the tape if needed, forgetting any decoded
+/// instruction at that address and noting the change for reset().
+#[inline(always)]
+fn store<W: Word>(cells: &mut Vec<W>, decoded: &mut [Option<Instruction>], program: &mut Program<W>,
+                  i: usize, v: W) {
+    if let Some(slot) = decoded.get_mut(i) {
+        *slot = None;
+    }
+    if i >= cells.len() {
+        cells.resize(i + 1, W::from_i64(0));
+    }
+    let old = mem::replace(&mut cells[i], v);
+    program.record(cells, i, old);
+}
+
+impl<W: Word> IntcodeComp<W> {
+    /// Returns true if run_fast() can be used, see the module documentation.
+    #[inline]
+    pub(crate) fn can_run_fast(&self) -> bool {
+        self.tracer.is_none() && self.watchpoints.is_empty() && self.breakpoints.is_empty()
+            && matches!(self.tape, Memory::Dense(_))
+    }
+
+    /// Like run() without a deadline, for computers where can_run_fast() is
+    /// true.  The computer must already be in the Running state.
+    pub(crate) fn run_fast(&mut self, budget: Option<u64>, outputs: Option<usize>) {
+        let max_memory = self.max_memory;
+        let mut head = self.head;
+        let mut rel_base = self.rel_base;
+        let mut executed: u64 = 0;
+
+        let fault = {
+            let cells = match self.tape {
+                Memory::Dense(ref mut cells) => cells,
+                Memory::Sparse(_) => unreachable!("run_fast() needs dense memory"),
+            };
+            let decoded = &mut self.decoded;
+            let program = &mut self.program;
+            let inputs: &mut VecDeque<W> = &mut self.inputs;
+            let queue: &mut VecDeque<W> = &mut self.outputs;
+
+            macro_rules! tri {
+                ($e:expr) => {
+                    match $e {
+                        Ok(v) => v,
+                        Err(fault) => break Some(fault),
+                    }
+                };
+            }
+
+            loop {
+                if budget == Some(executed) {
+                    self.state = IntcodeState::Paused;
+                    break None;
+                }
+                executed += 1;
+
+                self.inst_head = head;
+                self.inst = load(cells, head);
+                let inst = match decoded.get(head) {
+                    Some(&Some(inst)) => inst,
+                    _ => {
+                        let inst = match self.inst.to_i64() {
+                            Some(value) => Instruction::decode(value),
+                            None => Err(DecodeError::UnknownOpcode),
+                        };
+                        let inst = tri!(inst.map_err(Fault::Decode));
+                        if head < DECODE_CACHE_CELLS && self.executed + executed >= DECODE_CACHE_AFTER {
+                            if head >= decoded.len() {
+                                let len = cmp::max(head + 1, cells.len());
+                                decoded.resize(len.min(DECODE_CACHE_CELLS), None);
+                            }
+                            decoded[head] = Some(inst);
+                        }
+                        inst
+                    },
+                };
+                self.mode = inst.modes;
+                let m = inst.modes;
+
+                match inst.opcode {
+                    Opcode::Add | Opcode::Mult => {
+                        let x = tri!(param(cells, head + 1, m[0], rel_base, max_memory));
+                        let y = tri!(param(cells, head + 2, m[1], rel_base, max_memory));
+                        let i = tri!(target(cells, head + 3, m[2], rel_base, max_memory));
+                        let add = inst.opcode == Opcode::Add;
+                        let v = match self.overflow {
+                            OverflowPolicy::Wrap if add => x.wrapping_add(&y),
+                            OverflowPolicy::Wrap => x.wrapping_mul(&y),
+                            OverflowPolicy::Saturate if add => x.saturating_add(&y),
+                            OverflowPolicy::Saturate => x.saturating_mul(&y),
+                            OverflowPolicy::Checked => {
+                                let v = if add { x.checked_add(&y) } else { x.checked_mul(&y) };
+                                match v {
+                                    Some(v) => v,
+                                    None => break Some(Fault::Overflow(x, y)),
+                                }
+                            },
+                        };
+                        store(cells, decoded, program, i, v);
+                        head += 4;
+                    },
+                    Opcode::Input => {
+                        let input = match inputs.pop_front() {
+                            Some(input) => Some(input),
+                            None => self.input_source.as_mut().and_then(|s| s.next_input()),
+                        };
+                        match input {
+                            Some(v) => {
+                                let i = tri!(target(cells, head + 1, m[0], rel_base, max_memory));
+                                store(cells, decoded, program, i, v);
+                                head += 2;
+                            },
+                            None => {
+                                // Leave the head on the instruction, so it's
+                                // executed from scratch once input arrives.
+                                self.state = IntcodeState::NeedsInput;
+                                break None;
+                            },
+                        }
+                    },
+                    Opcode::Output => {
+                        let v = tri!(param(cells, head + 1, m[0], rel_base, max_memory));
+                        head += 2;
+                        match self.output_sink {
+                            Some(ref mut sink) => sink.write_output(v),
+                            None => {
+                                queue.push_back(v);
+                                if outputs.is_some_and(|n| queue.len() >= n) {
+                                    self.state = IntcodeState::Ready;
+                                    break None;
+                                }
+                            },
+                        }
+                    },
+                    Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                        let x = tri!(param(cells, head + 1, m[0], rel_base, max_memory));
+                        let y = tri!(param(cells, head + 2, m[1], rel_base, max_memory));
+                        if (x != W::from_i64(0)) == (inst.opcode == Opcode::JumpIfTrue) {
+                            head = tri!(address(y, max_memory));
+                        } else {
+                            head += 3;
+                        }
+                    },
+                    Opcode::LessThan | Opcode::Equals => {
+                        let x = tri!(param(cells, head + 1, m[0], rel_base, max_memory));
+                        let y = tri!(param(cells, head + 2, m[1], rel_base, max_memory));
+                        let i = tri!(target(cells, head + 3, m[2], rel_base, max_memory));
+                        let holds = if inst.opcode == Opcode::LessThan { x < y } else { x == y };
+                        store(cells, decoded, program, i, W::from_i64(holds as i64));
+                        head += 4;
+                    },
+                    Opcode::RelAdjust => {
+                        let x = tri!(param(cells, head + 1, m[0], rel_base, max_memory));
+                        match x.to_i64().and_then(|x| rel_base.checked_add(x)) {
+                            Some(r) => rel_base = r,
+                            None => break Some(Fault::Overflow(W::from_i64(rel_base), x)),
+                        }
+                        head += 2;
+                    },
+                    Opcode::Halt => {
+                        self.state = IntcodeState::Finished;
+                        break None;
+                    },
+                }
+            }
+        };
+
+        self.head = head;
+        self.rel_base = rel_base;
+        self.executed += executed;
+        if let Some(fault) = fault {
+            let error = match fault {
+                Fault::Decode(e) => self.decode_error(e),
+                Fault::Address(pos) => self.address_error(pos),
+                Fault::Overflow(x, y) => self.overflow_error(x, y),
+            };
+            self.state = IntcodeState::Err(error);
+        }
+    }
+}
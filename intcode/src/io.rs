@@ -0,0 +1,152 @@
+//! Sources of input and destinations for output, see
+//! IntcodeComp::with_input() and IntcodeComp::with_output().
+//!
+//! By default a computer reads from the queue filled by push_input(), and
+//! writes to the queue drained by pop_output().  Any IntcodeInput or
+//! IntcodeOutput can be attached instead, which lets a controller feed the
+//! computer directly rather than going through a start() loop.  The traits are
+//! implemented for:
+//!  * `VecDeque` - a plain queue.
+//!  * Closures - `FnMut() -> Option<W>` for input, `FnMut(W)` for output.
+//!  * `Rc<RefCell<T>>` of any of these, so a source can still be reached after
+//!    it's attached.  Sharing one `VecDeque` between two computers connects
+//!    the output of one to the input of the other.
+//!  * Channels - a `Receiver` is read without blocking, and a `Sender` ignores
+//!    a disconnected receiver.
+//!  * Iterators, wrapped in an IterInput.
+//!  * Writers such as files, wrapped in a WriteOutput.
+//!
+//! Both traits take the computer's word type as a parameter, which is i64
+//! unless the computer was built with another Word.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Something the computer can read input from.
+pub trait IntcodeInput<W = i64> {
+    /// Returns the next input value, or None if there isn't one yet.  The
+    /// computer then stops in the NeedsInput state, and asks again when it's
+    /// resumed.
+    fn next_input(&mut self) -> Option<W>;
+}
+
+/// Something the computer can write output to.
+pub trait IntcodeOutput<W = i64> {
+    /// Accepts a single output value.
+    fn write_output(&mut self, value: W);
+}
+
+impl<W> IntcodeInput<W> for VecDeque<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.pop_front()
+    }
+}
+
+impl<W> IntcodeOutput<W> for VecDeque<W> {
+    fn write_output(&mut self, value: W) {
+        self.push_back(value);
+    }
+}
+
+impl<W, F: FnMut() -> Option<W>> IntcodeInput<W> for F {
+    fn next_input(&mut self) -> Option<W> {
+        self()
+    }
+}
+
+impl<W, F: FnMut(W)> IntcodeOutput<W> for F {
+    fn write_output(&mut self, value: W) {
+        self(value)
+    }
+}
+
+impl<W, T: IntcodeInput<W>> IntcodeInput<W> for Rc<RefCell<T>> {
+    fn next_input(&mut self) -> Option<W> {
+        self.borrow_mut().next_input()
+    }
+}
+
+impl<W, T: IntcodeOutput<W>> IntcodeOutput<W> for Rc<RefCell<T>> {
+    fn write_output(&mut self, value: W) {
+        self.borrow_mut().write_output(value)
+    }
+}
+
+/// Never blocks; an empty channel stops the computer in the NeedsInput state.
+/// To wait for a value on another thread instead, attach a closure such as
+/// `move || rx.recv().ok()`.
+impl<W> IntcodeInput<W> for Receiver<W> {
+    fn next_input(&mut self) -> Option<W> {
+        self.try_recv().ok()
+    }
+}
+
+/// Values sent after the receiver has hung up are dropped.
+impl<W> IntcodeOutput<W> for Sender<W> {
+    fn write_output(&mut self, value: W) {
+        let _ = self.send(value);
+    }
+}
+
+/// Reads input from an iterator, until it runs out.
+///
+/// # Example
+///
+/// ```
+/// use intcode::io::IterInput;
+///
+/// // This tape outputs the sum of two inputs.
+/// let tape: Vec<i64> = vec![3,11,3,12,1,11,12,11,4,11,99,0,0];
+/// let mut comp = intcode::IntcodeComp::new(tape)
+///     .with_input(IterInput((1..).map(|i| i * 10)));
+/// comp.start();
+/// assert_eq!(comp.pop_output(), Some(30));
+/// ```
+pub struct IterInput<I>(pub I);
+
+impl<W, I: Iterator<Item = W>> IntcodeInput<W> for IterInput<I> {
+    fn next_input(&mut self) -> Option<W> {
+        self.0.next()
+    }
+}
+
+/// Writes each output value on its own line.  Nothing more is written after
+/// the first error, which is kept for the caller to check.
+pub struct WriteOutput<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> WriteOutput<W> {
+    /// Creates an output that writes to the given writer.  Values are written
+    /// as they're output, so wrap slow writers in a BufWriter.
+    pub fn new(writer: W) -> WriteOutput<W> {
+        WriteOutput { writer, error: None }
+    }
+
+    /// Returns the error that stopped the output from being written, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<V: fmt::Display, W: Write> IntcodeOutput<V> for WriteOutput<W> {
+    fn write_output(&mut self, value: V) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(e) = writeln!(self.writer, "{}", value) {
+            self.error = Some(e);
+        }
+    }
+}
@@ -1,10 +1,45 @@
 #![crate_name = "intcode"]
 
+use std::array;
+use std::cmp;
+use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+use std::time::Instant;
 
-#[derive(Debug, PartialEq)]
-pub enum IntcodeState {
-    /// Indicates the computer is initialized and ready to start.
+pub mod ascii;
+pub mod asm;
+pub mod binary;
+pub mod disasm;
+mod fast;
+pub mod io;
+pub mod memory;
+pub mod net;
+pub mod persist;
+pub mod pipeline;
+pub mod tape;
+pub mod trace;
+pub mod word;
+
+#[cfg(feature = "bigint")]
+extern crate num_bigint;
+#[cfg(feature = "bigint")]
+extern crate num_traits;
+
+use memory::Memory;
+use trace::Tracer;
+pub use io::{IntcodeInput, IntcodeOutput};
+pub use memory::MemoryKind;
+pub use tape::{load_tape, parse_tape, TapeParseError};
+pub use word::Word;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntcodeState<W = i64> {
+    /// Indicates the computer is initialized and ready to start, or that it
+    /// has executed a single instruction via step() and is ready to resume.
     Ready,
     /// Indicates the computer is currently running.
     Running,
@@ -12,13 +47,630 @@ pub enum IntcodeState {
     /// the push_input() function.  Then call continue() to resume execution
     /// from where the computer left off.
     NeedsInput,
+    /// Indicates the computer has paused because its head reached a
+    /// breakpoint.  The breakpoint's address is included.  The instruction at
+    /// the breakpoint hasn't been executed yet; calling start() again executes
+    /// it and carries on.
+    Breakpoint(usize),
+    /// Indicates the computer has paused because an instruction accessed
+    /// memory covered by a pausing watchpoint.  The access is included; if
+    /// the instruction both read and wrote watched memory, it's the write.
+    /// The instruction has already been executed; calling start() again
+    /// carries on from the next one.
+    Watchpoint(WatchEvent<W>),
+    /// Indicates the computer has paused because it used up the instruction
+    /// budget given to run_for(), or passed the deadline given to
+    /// run_until().  Calling start() (or either of those) again carries on
+    /// from where it left off.
+    Paused,
     /// Indicates the computer has successfully finished.
     Finished,
-    /// Indicates the computer encountered an unexpected error.  An error
-    /// message will be included if this result value is returned.
-    Err(&'static str),
+    /// Indicates the computer encountered an unexpected error.  The error
+    /// describes what went wrong and where on the tape it happened.
+    Err(IntcodeError<W>),
+}
+
+/// Describes a fatal error encountered while executing a tape.  Every variant
+/// records the position of the offending instruction (head), the raw value of
+/// that instruction, and the relative base at the time of the error.
+/// 
+/// # Example
+/// 
+/// ```
+/// // The second instruction uses opcode 42, which doesn't exist.
+/// let tape: Vec<i64> = vec![1101,1,1,0,42,99];
+/// let mut comp = intcode::IntcodeComp::new(tape);
+/// comp.start();
+/// assert_eq!(*comp.state(), intcode::IntcodeState::Err(
+///     intcode::IntcodeError::UnknownOpcode { head: 4, instruction: 42, rel_base: 0 }));
+/// 
+/// // Relative mode with a negative offset points before the start of the tape.
+/// let tape: Vec<i64> = vec![204,-1,99];
+/// let mut comp = intcode::IntcodeComp::new(tape);
+/// comp.start();
+/// match comp.state() {
+///     intcode::IntcodeState::Err(e) => {
+///         assert_eq!(e.head(), 0);
+///         assert_eq!(e.instruction(), 204);
+///         assert_eq!(e.to_string(),
+///                    "negative address -1 (head 0, instruction 204, relative base 0)");
+///     },
+///     s => panic!("Unexpected state: {:?}", s),
+/// }
+/// 
+/// // A relative base and offset that overflow together still fault, whether
+/// // or not the computer is being debugged.
+/// let tape: Vec<i64> = vec![109,i64::MIN,204,i64::MIN,99];
+/// for debugging in vec![false, true] {
+///     let mut comp = intcode::IntcodeComp::new(tape.clone());
+///     if debugging {
+///         comp.add_breakpoint(1000);
+///     }
+///     comp.start();
+///     assert_eq!(*comp.state(), intcode::IntcodeState::Err(intcode::IntcodeError::NegativeAddress {
+///         head: 2, instruction: 204, rel_base: i64::MIN, address: i64::MIN }));
+///     assert_eq!(comp.pop_output(), None);
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntcodeError<W = i64> {
+    /// The rightmost two digits of the instruction aren't a known opcode.
+    UnknownOpcode { head: usize, instruction: W, rel_base: i64 },
+    /// One of the instruction's mode digits isn't 0, 1 or 2.  The offending
+    /// digit is included.
+    UnknownMode { head: usize, instruction: W, rel_base: i64, mode: i64 },
+    /// An output parameter was given in immediate mode.
+    ImmediateWrite { head: usize, instruction: W, rel_base: i64 },
+    /// A parameter or jump target resolved to a negative address.  The
+    /// offending address is included; if adding a relative-mode offset to the
+    /// relative base overflowed, the offset is included instead.
+    NegativeAddress { head: usize, instruction: W, rel_base: i64, address: W },
+    /// A parameter or jump target resolved to an address at or beyond the
+    /// computer's memory limit.  The offending address (or overflowing
+    /// offset, as for NegativeAddress) and the limit are included.
+    AddressOutOfRange { head: usize, instruction: W, rel_base: i64, address: W, limit: usize },
+    /// An add or multiply overflowed under OverflowPolicy::Checked, or a
+    /// relative adjust took the relative base beyond what an i64 can hold.
+    /// The two values being combined are included.
+    Overflow { head: usize, instruction: W, rel_base: i64, operands: (W, W) },
+}
+
+impl<W: Word> IntcodeError<W> {
+    /// Returns the position of the instruction that caused the error.
+    pub fn head(&self) -> usize {
+        match *self {
+            IntcodeError::UnknownOpcode { head, .. } => head,
+            IntcodeError::UnknownMode { head, .. } => head,
+            IntcodeError::ImmediateWrite { head, .. } => head,
+            IntcodeError::NegativeAddress { head, .. } => head,
+            IntcodeError::AddressOutOfRange { head, .. } => head,
+            IntcodeError::Overflow { head, .. } => head,
+        }
+    }
+
+    /// Returns the raw value of the instruction that caused the error.
+    pub fn instruction(&self) -> W {
+        match *self {
+            IntcodeError::UnknownOpcode { ref instruction, .. } => instruction.clone(),
+            IntcodeError::UnknownMode { ref instruction, .. } => instruction.clone(),
+            IntcodeError::ImmediateWrite { ref instruction, .. } => instruction.clone(),
+            IntcodeError::NegativeAddress { ref instruction, .. } => instruction.clone(),
+            IntcodeError::AddressOutOfRange { ref instruction, .. } => instruction.clone(),
+            IntcodeError::Overflow { ref instruction, .. } => instruction.clone(),
+        }
+    }
+
+    /// Returns the relative base at the time of the error.
+    pub fn rel_base(&self) -> i64 {
+        match *self {
+            IntcodeError::UnknownOpcode { rel_base, .. } => rel_base,
+            IntcodeError::UnknownMode { rel_base, .. } => rel_base,
+            IntcodeError::ImmediateWrite { rel_base, .. } => rel_base,
+            IntcodeError::NegativeAddress { rel_base, .. } => rel_base,
+            IntcodeError::AddressOutOfRange { rel_base, .. } => rel_base,
+            IntcodeError::Overflow { rel_base, .. } => rel_base,
+        }
+    }
+}
+
+impl<W: Word> fmt::Display for IntcodeError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IntcodeError::UnknownOpcode { ref instruction, .. } => match instruction.to_i64() {
+                Some(i) => write!(f, "unknown opcode {}", i % 100)?,
+                None => write!(f, "unknown opcode")?,
+            },
+            IntcodeError::UnknownMode { mode, .. } =>
+                write!(f, "unknown parameter mode {}", mode)?,
+            IntcodeError::ImmediateWrite { .. } =>
+                write!(f, "immediate mode used for an output parameter")?,
+            IntcodeError::NegativeAddress { ref address, .. } =>
+                write!(f, "negative address {}", address)?,
+            IntcodeError::AddressOutOfRange { ref address, limit, .. } =>
+                write!(f, "address {} is beyond the memory limit of {} cells", address, limit)?,
+            IntcodeError::Overflow { operands: (ref x, ref y), .. } =>
+                write!(f, "overflow combining {} and {}", x, y)?,
+        }
+        write!(f, " (head {}, instruction {}, relative base {})",
+               self.head(), self.instruction(), self.rel_base())
+    }
+}
+
+impl<W: Word> error::Error for IntcodeError<W> {}
+
+/// The values a computer output before it finished, when they don't make up a
+/// whole record.  See IntcodeComp::run_until_outputs().
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PartialRecord<W = i64> {
+    /// The number of values in a whole record.
+    pub expected: usize,
+    /// The values that were output.
+    pub values: Vec<W>,
+}
+
+impl<W: Word> fmt::Display for PartialRecord<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "computer finished partway through a record of {} values: {:?}",
+               self.expected, self.values)
+    }
+}
+
+impl<W: Word> error::Error for PartialRecord<W> {}
+
+/// Something the handler given to IntcodeComp::run_with() needs to deal with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IoEvent<W = i64> {
+    /// The computer needs input before it can carry on.
+    NeedsInput,
+    /// The computer output a value.
+    Output(W),
+}
+
+/// A copy of everything that determines how an IntcodeComp will carry on
+/// running: its tape, head, relative base, state, memory limit, overflow
+/// policy and input and output queues.  See IntcodeComp::snapshot().
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot<W = i64> {
+    tape: Memory<W>,
+    head: usize,
+    inst_head: usize,
+    inst: W,
+    mode: [Mode; 3],
+    rel_base: i64,
+    max_memory: usize,
+    overflow: OverflowPolicy,
+    state: IntcodeState<W>,
+    inputs: VecDeque<W>,
+    outputs: VecDeque<W>,
+}
+
+/// What add and multiply do when their result doesn't fit in a cell.  See
+/// IntcodeComp::with_overflow_policy().
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// Wrap around at the bounds of the word type.  This is the default.
+    #[default]
+    Wrap,
+    /// Stop the computer with an Overflow error.
+    Checked,
+    /// Clamp the result to the largest or smallest value a cell can hold.
+    Saturate,
+}
+
+/// The kinds of memory access a watchpoint can react to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+/// Describes a single memory access that triggered a watchpoint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WatchEvent<W = i64> {
+    /// The position of the instruction that accessed memory.
+    pub ip: usize,
+    /// The address that was accessed.
+    pub addr: usize,
+    /// Either Read or Write.
+    pub access: Access,
+    /// The value in the cell before the access.
+    pub old: W,
+    /// The value in the cell after the access.  Same as old for reads.
+    pub new: W,
+}
+
+/// A function called by a watchpoint each time it's triggered.
+pub type WatchCallback<W = i64> = Box<dyn FnMut(&WatchEvent<W>)>;
+
+/// What a watchpoint does when it's triggered.
+pub enum WatchAction<W = i64> {
+    /// Pause the computer in the Watchpoint state once the instruction that
+    /// triggered it has finished.
+    Pause,
+    /// Call the given function, then carry on running.
+    Callback(WatchCallback<W>),
+}
+
+/// A range of addresses being watched, see IntcodeComp::add_watchpoint().
+struct Watchpoint<W> {
+    id: usize,
+    range: Range<usize>,
+    access: Access,
+    action: WatchAction<W>,
+}
+
+/// What reset() needs to put a computer's tape back the way it started.
+#[derive(Clone)]
+enum Program<W> {
+    /// A copy of the starting tape.
+    Copied(Memory<W>),
+    /// The length of a dense starting tape, and the starting value of every
+    /// cell below that length that has been written since, in the order they
+    /// were written.  Most computers are run once and thrown away, so this is
+    /// kept instead of a copy until it grows as long as the tape.
+    Changes(usize, Vec<(usize, W)>),
+}
+
+impl<W: Clone> Program<W> {
+    /// Returns a Program for a computer starting with the given tape.
+    fn of(tape: &Memory<W>) -> Program<W> {
+        match *tape {
+            Memory::Dense(ref cells) => Program::Changes(cells.len(), Vec::new()),
+            Memory::Sparse(_) => Program::Copied(tape.clone()),
+        }
+    }
+
+    /// Notes that the cell at i of a dense tape, which now holds the given
+    /// cells, has been changed from old.
+    #[inline(always)]
+    fn record(&mut self, cells: &[W], i: usize, old: W) {
+        let full = match *self {
+            Program::Changes(len, ref mut changes) if i < len => {
+                if changes.capacity() == 0 {
+                    // Start with room for a good share of the tape, rather
+                    // than growing a few changes at a time.
+                    changes.reserve(len / 4);
+                }
+                changes.push((i, old));
+                changes.len() >= len
+            },
+            _ => false,
+        };
+        if full {
+            self.copy(cells);
+        }
+    }
+
+    /// Switches to keeping a copy of the starting tape, given the current
+    /// cells of a dense tape.
+    fn copy(&mut self, cells: &[W]) {
+        if let Program::Changes(len, ref changes) = *self {
+            let mut program = cells[..len].to_vec();
+            for &(i, ref old) in changes.iter().rev() {
+                program[i] = old.clone();
+            }
+            *self = Program::Copied(Memory::Dense(program));
+        }
+    }
+}
+
+/// The operation performed by an instruction, taken from the rightmost two
+/// digits of the instruction.  See IntcodeComp for a description of each.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Opcode {
+    Add,
+    Mult,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    RelAdjust,
+    Halt,
+}
+
+impl Opcode {
+    /// Returns the opcode with the given numeric code, if there is one.
+    pub const fn from_code(code: i64) -> Option<Opcode> {
+        match code {
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Mult),
+            3 => Some(Opcode::Input),
+            4 => Some(Opcode::Output),
+            5 => Some(Opcode::JumpIfTrue),
+            6 => Some(Opcode::JumpIfFalse),
+            7 => Some(Opcode::LessThan),
+            8 => Some(Opcode::Equals),
+            9 => Some(Opcode::RelAdjust),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        }
+    }
+
+    /// Returns the numeric code for this opcode.
+    pub fn code(self) -> i64 {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Mult => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::RelAdjust => 9,
+            Opcode::Halt => 99,
+        }
+    }
+
+    /// Returns the short name used for this opcode in listings.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Mult => "MUL",
+            Opcode::Input => "IN",
+            Opcode::Output => "OUT",
+            Opcode::JumpIfTrue => "JT",
+            Opcode::JumpIfFalse => "JF",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::RelAdjust => "ARB",
+            Opcode::Halt => "HLT",
+        }
+    }
+
+    /// Returns the opcode with the given listing name, ignoring case.
+    pub fn from_mnemonic(name: &str) -> Option<Opcode> {
+        let all = [Opcode::Add, Opcode::Mult, Opcode::Input, Opcode::Output,
+                   Opcode::JumpIfTrue, Opcode::JumpIfFalse, Opcode::LessThan,
+                   Opcode::Equals, Opcode::RelAdjust, Opcode::Halt];
+        all.iter().cloned().find(|op| op.mnemonic().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the number of parameters that follow this opcode on the tape.
+    pub const fn num_params(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Mult | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::RelAdjust => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    /// Returns the index of the parameter this opcode writes to, if any.
+    pub const fn output_param(self) -> Option<usize> {
+        match self {
+            Opcode::Add | Opcode::Mult | Opcode::LessThan | Opcode::Equals => Some(2),
+            Opcode::Input => Some(0),
+            _ => None,
+        }
+    }
+}
+
+/// How an instruction's parameter should be interpreted.  See IntcodeComp for
+/// a description of each.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
 }
 
+impl Mode {
+    /// Returns the mode with the given digit, if there is one.
+    pub fn from_digit(digit: i64) -> Option<Mode> {
+        match digit {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+
+    /// Returns the digit for this mode.
+    pub fn digit(self) -> i64 {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+/// The reasons a value on the tape may fail to decode as an instruction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The rightmost two digits aren't a known opcode.
+    UnknownOpcode,
+    /// One of the mode digits isn't a known mode.  The digit is included.
+    UnknownMode(i64),
+    /// An output parameter was given in immediate mode.
+    ImmediateWrite,
+}
+
+/// The modes given by a number made of up to three mode digits, as found
+/// after the opcode of an instruction.  Digits that aren't a mode are left as
+/// Position, and flagged in unknown (bit i for parameter i).  Immediate modes
+/// are flagged in immediate, so output parameters can be checked.
+#[derive(Clone, Copy)]
+struct ModeDigits {
+    modes: [Mode; 3],
+    unknown: u8,
+    immediate: u8,
+}
+
+/// ModeDigits for every number of up to three digits, counting only the
+/// first 0, 1, 2 or 3 digits, so decoding an instruction is a lookup rather
+/// than a division per parameter.
+static MODE_DIGITS: [[ModeDigits; 1000]; 4] = mode_digits();
+
+/// Builds MODE_DIGITS.
+const fn mode_digits() -> [[ModeDigits; 1000]; 4] {
+    let mut table = [[ModeDigits { modes: [Mode::Position; 3], unknown: 0, immediate: 0 }; 1000]; 4];
+    let mut params = 0;
+    while params < 4 {
+        let mut n = 0;
+        while n < 1000 {
+            let entry = &mut table[params][n];
+            let mut digits = n;
+            let mut i = 0;
+            while i < params {
+                match digits % 10 {
+                    0 => (),
+                    1 => {
+                        entry.modes[i] = Mode::Immediate;
+                        entry.immediate |= 1 << i;
+                    },
+                    2 => entry.modes[i] = Mode::Relative,
+                    _ => entry.unknown |= 1 << i,
+                }
+                digits /= 10;
+                i += 1;
+            }
+            n += 1;
+        }
+        params += 1;
+    }
+    table
+}
+
+/// What decoding an instruction needs to know about its opcode.
+#[derive(Clone, Copy)]
+struct OpcodeInfo {
+    opcode: Opcode,
+    num_params: usize,
+    /// Bit i is set if parameter i is an output parameter.
+    output: u8,
+}
+
+/// OpcodeInfo for every two-digit code that's a known opcode, so decoding an
+/// instruction doesn't need to match on it.
+static OPCODE_INFO: [Option<OpcodeInfo>; 100] = opcode_info();
+
+/// Builds OPCODE_INFO.
+const fn opcode_info() -> [Option<OpcodeInfo>; 100] {
+    let mut table = [None; 100];
+    let mut code = 0;
+    while code < 100 {
+        if let Some(opcode) = Opcode::from_code(code as i64) {
+            let output = match opcode.output_param() {
+                Some(i) => 1 << i,
+                None => 0,
+            };
+            table[code] = Some(OpcodeInfo { opcode, num_params: opcode.num_params(), output });
+        }
+        code += 1;
+    }
+    table
+}
+
+/// A decoded instruction: an opcode plus the mode of each of its parameters.
+/// Modes beyond the opcode's parameter count are always Position.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub modes: [Mode; 3],
+}
+
+impl Instruction {
+    /// Decodes a value from the tape into an instruction.  Only the mode
+    /// digits for parameters the opcode actually takes are checked; any
+    /// further digits are ignored.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use intcode::{Instruction, Mode, Opcode};
+    /// let inst = Instruction::decode(1002).unwrap();
+    /// assert_eq!(inst.opcode, Opcode::Mult);
+    /// assert_eq!(inst.modes, [Mode::Position, Mode::Immediate, Mode::Position]);
+    /// assert_eq!(inst.encode(), 1002);
+    /// assert_eq!(Instruction::decode(11101), Err(intcode::DecodeError::ImmediateWrite));
+    /// ```
+    #[inline]
+    pub fn decode(value: i64) -> Result<Instruction, DecodeError> {
+        // Only the rightmost five digits can matter, and dividing a u32 is
+        // cheaper.  A negative value is never a known opcode.
+        let value = match value {
+            0..=99_999 => value as u32,
+            100_000.. => (value % 100_000) as u32,
+            _ => return Err(DecodeError::UnknownOpcode),
+        };
+        let info = match OPCODE_INFO[(value % 100) as usize] {
+            Some(info) => info,
+            None => return Err(DecodeError::UnknownOpcode),
+        };
+        // Only the digits for the parameters the opcode takes count, see
+        // MODE_DIGITS.
+        let digits = value / 100;
+        let modes = &MODE_DIGITS[info.num_params][digits as usize];
+        if modes.unknown != 0 {
+            let place = [1, 10, 100][modes.unknown.trailing_zeros() as usize];
+            return Err(DecodeError::UnknownMode((digits / place % 10) as i64));
+        }
+        if modes.immediate & info.output != 0 {
+            return Err(DecodeError::ImmediateWrite);
+        }
+        Ok(Instruction { opcode: info.opcode, modes: modes.modes })
+    }
+
+    /// Returns the canonical tape value for this instruction.
+    pub fn encode(&self) -> i64 {
+        let mut value = 0;
+        for mode in self.modes[..self.opcode.num_params()].iter().rev() {
+            value = value * 10 + mode.digit();
+        }
+        value * 100 + self.opcode.code()
+    }
+
+    /// Returns the number of cells the instruction occupies on the tape,
+    /// including its parameters.
+    pub fn size(&self) -> usize {
+        1 + self.opcode.num_params()
+    }
+}
+
+/// How many instructions run_until() executes between checks of the clock.
+pub const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// The largest number of cells a computer's tape may grow to, unless a
+/// different limit is given via IntcodeComp::with_max_memory().  With sparse
+/// memory this is a limit on addresses rather than on allocation.
+pub const DEFAULT_MAX_MEMORY: usize = 1 << 24;
+
+/// Instructions at addresses below this are decoded once and cached, rather
+/// than decoded every time they're executed.  Instructions further out (which
+/// only self-modifying tapes reach) are decoded each time.  Writing to a cell
+/// forgets whatever was decoded there.
+/// 
+/// # Example
+/// 
+/// ```
+/// // This tape outputs 1+1, turns its first instruction into a multiply, then
+/// // runs it again.
+/// let tape: Vec<i64> = vec![1101,1,1,100, 4,100, 1101,1100,2,0,
+///                           1001,102,1,102, 1008,102,2,103, 1006,103,0, 99];
+/// let mut comp = intcode::IntcodeComp::new(tape);
+/// comp.start();
+/// assert_eq!(comp.pop_output(), Some(2));
+/// assert_eq!(comp.pop_output(), Some(1));
+/// 
+/// // Resetting puts the add back.
+/// comp.reset();
+/// comp.start();
+/// assert_eq!(comp.pop_output(), Some(2));
+/// assert_eq!(comp.pop_output(), Some(1));
+/// ```
+pub const DECODE_CACHE_CELLS: usize = 1 << 16;
+
+/// A computer only starts caching decoded instructions once it has executed
+/// this many, counting across reset().  Most short-lived computers execute
+/// each instruction about once, so filling a cache would cost them more than
+/// it saves.
+pub const DECODE_CACHE_AFTER: u64 = 64;
+
 /// Contains all the state necessary for an Intcode Computer (an FSA written
 /// for AoC 2019).
 /// 
@@ -64,24 +716,62 @@ pub enum IntcodeState {
 /// is an 'add' instruction where all parameters are in the positional mode
 /// (this is equivalent to '00001').  Similarly, 1102 is equivalent to 01102.
 /// Output parameters will always be in 'positional' mode.
-pub struct IntcodeComp {
+/// 
+/// Each cell of the tape is a Word, i64 unless another type is chosen (see
+/// the word module).  By default, add and multiply wrap around when the result
+/// doesn't fit in a cell; with_overflow_policy() can change that.
+pub struct IntcodeComp<W = i64> {
     /// The 'tape' that contains the instructions for the computer.
-    tape: Vec<i64>,
+    tape: Memory<W>,
+    /// The tape the computer was created with, which reset() goes back to.
+    program: Program<W>,
     /// Points to the value on the tape that is about to be read and processed.
     head: usize,
-    /// The current parameter mode of the computer.
-    mode: i64,
+    /// The position of the instruction currently being executed.
+    inst_head: usize,
+    /// The raw value of the instruction currently being executed.
+    inst: W,
+    /// The parameter modes of the instruction currently being executed.
+    mode: [Mode; 3],
+    /// Instructions that have already been decoded, indexed by address.  An
+    /// entry is cleared whenever its cell is written, so self-modifying tapes
+    /// are decoded afresh.  Empty until DECODE_CACHE_AFTER instructions have
+    /// been executed.
+    decoded: Vec<Option<Instruction>>,
+    /// The number of instructions executed, see DECODE_CACHE_AFTER.
+    executed: u64,
     /// The starting point for any relative-mode parameters.
     rel_base: i64,
+    /// The number of cells the tape is allowed to grow to.  Instructions that
+    /// address memory at or beyond this limit fail with an error.
+    max_memory: usize,
+    /// What add and multiply do when their result doesn't fit in a cell.
+    overflow: OverflowPolicy,
     /// Indicates the current result
-    state: IntcodeState,
+    state: IntcodeState<W>,
     /// A queue of inputs that have been provided to the computer.
-    inputs: VecDeque<i64>,
+    inputs: VecDeque<W>,
     /// A queue of outputs generated by the computer.
-    outputs: VecDeque<i64>,
+    outputs: VecDeque<W>,
+    /// Addresses where start() should pause before executing an instruction.
+    breakpoints: HashSet<usize>,
+    /// Ranges of memory that trigger an action when accessed.
+    watchpoints: Vec<Watchpoint<W>>,
+    /// The id to give the next watchpoint that's added.
+    next_watch_id: usize,
+    /// The access to report for a pausing watchpoint triggered during the
+    /// current instruction, if any: its write if it made one, otherwise its
+    /// first read.
+    watch_hit: Option<WatchEvent<W>>,
+    /// Records every executed instruction, if tracing is enabled.
+    tracer: Option<Tracer<W>>,
+    /// Where input comes from once the input queue is empty, if anywhere.
+    input_source: Option<Box<dyn IntcodeInput<W>>>,
+    /// Where output goes instead of the output queue, if anywhere.
+    output_sink: Option<Box<dyn IntcodeOutput<W>>>,
 }
 
-impl IntcodeComp {
+impl<W: Word> IntcodeComp<W> {
     /// Constructs and returns a new Intcode Computer, using a given tape as input.
     /// 
     /// # Examples
@@ -92,53 +782,894 @@ impl IntcodeComp {
     /// comp.start();
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// ```
-    pub fn new(t: Vec<i64>) -> IntcodeComp {
+    pub fn new(t: Vec<W>) -> IntcodeComp<W> {
         IntcodeComp{
-            tape: t,
+            program: Program::Changes(t.len(), Vec::new()),
+            tape: Memory::Dense(t),
             head: 0,
-            mode: 0,
+            inst_head: 0,
+            inst: W::from_i64(0),
+            mode: [Mode::Position; 3],
+            decoded: Vec::new(),
+            executed: 0,
             rel_base: 0,
+            max_memory: DEFAULT_MAX_MEMORY,
+            overflow: OverflowPolicy::default(),
             state: IntcodeState::Ready,
             inputs: VecDeque::new(),
             outputs: VecDeque::new(),
+            breakpoints: HashSet::new(),
+            watchpoints: Vec::new(),
+            next_watch_id: 0,
+            watch_hit: None,
+            tracer: None,
+            input_source: None,
+            output_sink: None,
+        }
+    }
+
+    /// Consumes the computer and returns it with a different memory limit.
+    /// Instructions that read, write or jump to an address at or beyond the
+    /// limit stop the computer with an AddressOutOfRange error, rather than
+    /// growing the tape without bound.  The limit is never smaller than the
+    /// initial tape.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// // This tape tries to write to address 1000.
+    /// let tape: Vec<i64> = vec![1101,1,1,1000,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape).with_max_memory(100);
+    /// comp.start();
+    /// match comp.state() {
+    ///     intcode::IntcodeState::Err(intcode::IntcodeError::AddressOutOfRange { address, limit, .. }) => {
+    ///         assert_eq!(*address, 1000);
+    ///         assert_eq!(*limit, 100);
+    ///     },
+    ///     s => panic!("Unexpected state: {:?}", s),
+    /// }
+    /// ```
+    pub fn with_max_memory(mut self, cells: usize) -> IntcodeComp<W> {
+        self.max_memory = cmp::max(cells, self.tape.len());
+        self
+    }
+
+    /// Consumes the computer and returns it with its memory stored in a
+    /// different way (see the memory module).  Sparse memory lets a tape
+    /// write to far-flung addresses without allocating everything in
+    /// between; pair it with a higher memory limit.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use intcode::MemoryKind;
+    /// 
+    /// // This tape copies address 1 to address 10^9 and back to address 0.
+    /// let tape: Vec<i64> = vec![1101,1000000000,0,1000000000,1001,1000000000,0,0,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape)
+    ///     .with_memory(MemoryKind::Sparse)
+    ///     .with_max_memory(1 << 40);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(comp.get(0), 1000000000);
+    /// assert_eq!(comp.memory().len(), 1000000001);
+    /// assert_eq!(comp.memory().allocated(), 2 * intcode::memory::PAGE_SIZE);
+    /// ```
+    pub fn with_memory(mut self, kind: MemoryKind) -> IntcodeComp<W> {
+        if self.tape.kind() != kind {
+            self.copy_program();
+            let tape = mem::replace(&mut self.tape, Memory::Dense(Vec::new()));
+            self.tape = Memory::new(kind, tape.to_vec());
+            if let Program::Copied(ref mut program) = self.program {
+                let cells = mem::replace(program, Memory::Dense(Vec::new())).to_vec();
+                *program = Memory::new(kind, cells);
+            }
         }
+        self
+    }
+
+    /// Consumes the computer and returns it with some cells of its tape
+    /// overwritten, see patch().  reset() undoes the patches.  Fails if
+    /// patch() would.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![1,0,0,0,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape).with_patches(&[(1, 4), (2, 4)]).unwrap();
+    /// comp.start();
+    /// assert_eq!(comp.get(0), 198);
+    /// ```
+    pub fn with_patches(mut self, patches: &[(usize, W)]) -> Result<IntcodeComp<W>, IntcodeError<W>> {
+        self.patch(patches)?;
+        Ok(self)
+    }
+
+    /// Consumes the computer and returns it with the given overflow policy,
+    /// which decides what add and multiply do when their result doesn't fit
+    /// in a cell.  Under OverflowPolicy::Checked the computer stops with an
+    /// Overflow error, which carries the two operands, so a wrong answer
+    /// can't be mistaken for a right one.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use intcode::{IntcodeComp, IntcodeError, IntcodeState, OverflowPolicy};
+    /// 
+    /// // This tape multiplies 2^62 by 2.
+    /// let tape: Vec<i64> = vec![1102,4611686018427387904,2,0,99];
+    /// let mut comp = IntcodeComp::new(tape.clone());
+    /// comp.start();
+    /// assert_eq!(comp.get(0), i64::MIN);
+    /// 
+    /// let mut comp = IntcodeComp::new(tape.clone()).with_overflow_policy(OverflowPolicy::Saturate);
+    /// comp.start();
+    /// assert_eq!(comp.get(0), i64::MAX);
+    /// 
+    /// let mut comp = IntcodeComp::new(tape.clone()).with_overflow_policy(OverflowPolicy::Checked);
+    /// comp.start();
+    /// match comp.state() {
+    ///     IntcodeState::Err(e) => {
+    ///         assert_eq!(*e, IntcodeError::Overflow {
+    ///             head: 0, instruction: 1102, rel_base: 0, operands: (1 << 62, 2) });
+    ///         assert_eq!(e.to_string(), "overflow combining 4611686018427387904 and 2 \
+    ///                                    (head 0, instruction 1102, relative base 0)");
+    ///     },
+    ///     s => panic!("Unexpected state: {:?}", s),
+    /// }
+    /// 
+    /// // A wider word holds the answer.
+    /// let tape: Vec<i128> = tape.into_iter().map(i128::from).collect();
+    /// let mut comp = IntcodeComp::new(tape).with_overflow_policy(OverflowPolicy::Checked);
+    /// comp.start();
+    /// assert_eq!(comp.get(0), 1 << 63);
+    /// ```
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> IntcodeComp<W> {
+        self.overflow = policy;
+        self
+    }
+
+    /// Consumes the computer and returns it with a source of input.  The
+    /// computer only reads from the source once everything given to
+    /// push_input() has been used, and stops in the NeedsInput state if the
+    /// source has nothing either.  See the io module for the sources
+    /// available.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// // This tape outputs double whatever it's given, until it's given 0.
+    /// let tape: Vec<i64> = vec![3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0];
+    /// let mut inputs = vec![0, 3, 2, 1];
+    /// let mut comp = intcode::IntcodeComp::new(tape)
+    ///     .with_input(move || inputs.pop());
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(comp.pop_output(), Some(2));
+    /// assert_eq!(comp.pop_output(), Some(4));
+    /// assert_eq!(comp.pop_output(), Some(6));
+    /// ```
+    pub fn with_input<I: IntcodeInput<W> + 'static>(mut self, source: I) -> IntcodeComp<W> {
+        self.input_source = Some(Box::new(source));
+        self
+    }
+
+    /// Consumes the computer and returns it with a destination for output.
+    /// Output goes to the destination instead of the queue read by
+    /// pop_output().  See the io module for the destinations available.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::collections::VecDeque;
+    /// use std::rc::Rc;
+    /// 
+    /// // This tape outputs double whatever it's given, until it's given 0.
+    /// let tape: Vec<i64> = vec![3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0];
+    /// 
+    /// // Connect the output of one computer to the input of another.
+    /// let pipe = Rc::new(RefCell::new(VecDeque::new()));
+    /// let mut first = intcode::IntcodeComp::new(tape.clone()).with_output(pipe.clone());
+    /// let mut second = intcode::IntcodeComp::new(tape).with_input(pipe.clone());
+    /// first.push_input(1);
+    /// first.push_input(5);
+    /// first.push_input(0);
+    /// first.start();
+    /// second.start();
+    /// assert_eq!(*second.state(), intcode::IntcodeState::NeedsInput);
+    /// second.push_input(0);
+    /// second.start();
+    /// assert_eq!(*second.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(second.pop_output(), Some(4));
+    /// assert_eq!(second.pop_output(), Some(20));
+    /// ```
+    pub fn with_output<O: IntcodeOutput<W> + 'static>(mut self, sink: O) -> IntcodeComp<W> {
+        self.output_sink = Some(Box::new(sink));
+        self
     }
 
     /// Starts the intcode computer.  This function blocks, and will only
-    /// return under 3 circumstances:
+    /// return under 5 circumstances:
     ///  * The computer is finished executing.
     ///  * The computer needs more input.  The user must add more input using
     ///    the push_input() function first, then call start() again to resume
     ///    execution.
-    ///  * The computer encountered a fatal error.
+    ///  * The computer's head reached a breakpoint.  Calling start() again
+    ///    resumes execution from the breakpoint.
+    ///  * An instruction triggered a pausing watchpoint.  Calling start()
+    ///    again resumes execution from the next instruction.
+    ///  * The computer encountered a fatal error.  The tape is left untouched
+    ///    from the point of the error onwards, and start() will refuse to
+    ///    run it again.
     /// 
     /// The state of the computer is an IntcodeState, and will be set to
-    /// one of three values based on the above situations (Finished,
-    /// NeedsInput, and Err respectively).
+    /// one of five values based on the above situations (Finished,
+    /// NeedsInput, Breakpoint, Watchpoint and Err respectively).
     pub fn start(&mut self) {
+        self.run(None, None, None);
+    }
+
+    /// Like start(), but executes at most the given number of instructions.
+    /// If the budget runs out first, the computer is left in the Paused
+    /// state, and calling start() or run_for() again carries on from there.
+    /// This keeps control with the caller even if a tape loops forever
+    /// without asking for input.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// // This tape jumps back to itself forever.
+    /// let tape: Vec<i64> = vec![1105,1,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.run_for(1000);
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Paused);
+    /// 
+    /// // A budget that's big enough lets the tape finish.
+    /// let tape: Vec<i64> = vec![1101,2,3,0,1102,2,3,4,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.run_for(2);
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Paused);
+    /// comp.run_for(1);
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// ```
+    pub fn run_for(&mut self, max_instructions: u64) {
+        self.run(Some(max_instructions), None, None);
+    }
+
+    /// Like start(), but stops once the given deadline has passed, leaving
+    /// the computer in the Paused state.  The clock is only checked every
+    /// DEADLINE_CHECK_INTERVAL instructions, so the deadline may be overrun
+    /// slightly.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// 
+    /// // This tape jumps back to itself forever.
+    /// let tape: Vec<i64> = vec![1105,1,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.run_until(Instant::now() + Duration::from_millis(10));
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Paused);
+    /// ```
+    pub fn run_until(&mut self, deadline: Instant) {
+        self.run(None, Some(deadline), None);
+    }
+
+    /// Runs the computer, handing every output and every request for input to
+    /// a handler, so that a whole controller fits in a single call.  Any value
+    /// the handler returns is queued as input.  Outputs are handled in order,
+    /// and all outputs made before a request for input are handled before it.
+    /// 
+    /// This returns when the computer stops for any reason other than
+    /// needing input, or when the handler returns None for a NeedsInput
+    /// event, leaving the computer in the NeedsInput state.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use intcode::IoEvent;
+    /// 
+    /// // This tape outputs double whatever it's given, until it's given 0.
+    /// let tape: Vec<i64> = vec![3,15,1006,15,14,1002,15,2,15,4,15,1105,1,0,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// let mut inputs = vec![0, 3, 2, 1];
+    /// let mut outputs = Vec::new();
+    /// comp.run_with(|event| match event {
+    ///     IoEvent::NeedsInput => inputs.pop(),
+    ///     IoEvent::Output(v) => {
+    ///         outputs.push(v);
+    ///         None
+    ///     },
+    /// });
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(outputs, vec![2, 4, 6]);
+    /// ```
+    pub fn run_with<F: FnMut(IoEvent<W>) -> Option<W>>(&mut self, mut handler: F) {
+        loop {
+            self.start();
+            while let Some(output) = self.outputs.pop_front() {
+                if let Some(input) = handler(IoEvent::Output(output)) {
+                    self.inputs.push_back(input);
+                }
+            }
+            if self.state != IntcodeState::NeedsInput {
+                return;
+            }
+            if self.inputs.is_empty() {
+                match handler(IoEvent::NeedsInput) {
+                    Some(input) => self.inputs.push_back(input),
+                    None => return,
+                }
+            }
+        }
+    }
+
+    /// Runs the computer until it has output a whole record of N values, and
+    /// returns the record.  The computer is left in the Ready state straight
+    /// after the instruction that completed the record, so calling this again
+    /// (or start()) carries on from there.
+    /// 
+    /// If the computer stops for any other reason first, this returns
+    /// Ok(None), leaving any values of an unfinished record in the output
+    /// queue.  That's expected when the computer needs input, but a computer
+    /// that has finished will never complete the record, so that's returned as
+    /// a PartialRecord error instead.
+    /// 
+    /// Records are gathered from the output queue, so this can't be used with
+    /// a computer that has an output destination attached.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![104,1,104,2,104,3,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// assert_eq!(comp.run_until_outputs::<2>(), Ok(Some([1, 2])));
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Ready);
+    /// let err = comp.run_until_outputs::<2>().unwrap_err();
+    /// assert_eq!(err.values, vec![3]);
+    /// assert_eq!(err.to_string(), "computer finished partway through a record of 2 values: [3]");
+    /// ```
+    pub fn run_until_outputs<const N: usize>(&mut self) -> Result<Option<[W; N]>, PartialRecord<W>> {
+        if self.outputs.len() < N {
+            self.run(None, None, Some(N));
+        }
+        if self.outputs.len() >= N {
+            return Ok(Some(array::from_fn(|_| self.outputs.pop_front().unwrap())));
+        }
+        if self.state == IntcodeState::Finished && !self.outputs.is_empty() {
+            return Err(PartialRecord { expected: N, values: self.outputs.drain(..).collect() });
+        }
+        Ok(None)
+    }
+
+    /// Helper function that runs the computer until it stops, runs out of
+    /// instructions in its budget, passes its deadline, or has at least a
+    /// given number of values in its output queue.
+    fn run(&mut self, budget: Option<u64>, deadline: Option<Instant>, outputs: Option<usize>) {
         match self.state {
             // If the computer is finished (or encountered an error), then
             // there's nothing to run.  So return right away.
             IntcodeState::Finished => return,
-            IntcodeState::Err(_s) => return,
+            IntcodeState::Err(_) => return,
             _ => (),
         }
-        let mut is_resume = self.state == IntcodeState::NeedsInput;
+        // If we're resuming from a breakpoint, the instruction under the head
+        // is the one that paused us.  Execute it before checking again.  The
+        // same goes for an input instruction that ran out of input, since
+        // any breakpoint on it has already been hit.
+        let mut skip_breakpoint = match self.state {
+            IntcodeState::Breakpoint(addr) => addr == self.head,
+            IntcodeState::NeedsInput => true,
+            _ => false,
+        };
         self.state = IntcodeState::Running;
-        while self.get(self.head) != 99 {
-            if is_resume {
-                // The computer previously paused because it needed more input.
-                // Pick up where it left off by calling input() directly.
-                self.input();
-                is_resume = false;
-                continue;
+        if deadline.is_none() && self.can_run_fast() {
+            // Nothing needs checking between instructions, see the fast
+            // module.
+            self.run_fast(budget, outputs);
+            return;
+        }
+        let mut executed: u64 = 0;
+        while self.state == IntcodeState::Running {
+            if !skip_breakpoint && !self.breakpoints.is_empty()
+                    && self.breakpoints.contains(&self.head) {
+                self.state = IntcodeState::Breakpoint(self.head);
+                return;
             }
-            self.execute_one();
-            if self.state != IntcodeState::Running {
+            skip_breakpoint = false;
+            if budget == Some(executed) {
+                self.state = IntcodeState::Paused;
                 return;
             }
+            if let Some(d) = deadline {
+                if executed.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= d {
+                    self.state = IntcodeState::Paused;
+                    return;
+                }
+            }
+            self.run_one();
+            executed += 1;
+            if let Some(n) = outputs {
+                if self.state == IntcodeState::Running && self.outputs.len() >= n {
+                    self.state = IntcodeState::Ready;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Executes a single instruction, ignoring any breakpoints.  Afterwards
+    /// the computer is left in the Ready state, unless the instruction
+    /// finished the tape, needed more input or failed.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![1101,2,3,0,1102,2,3,4,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.step();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Ready);
+    /// assert_eq!(comp.head(), 4);
+    /// assert_eq!(comp.get(0), 5);
+    /// assert_eq!(comp.get(4), 1102);
+    /// comp.step();
+    /// comp.step();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// ```
+    pub fn step(&mut self) {
+        match self.state {
+            IntcodeState::Finished => return,
+            IntcodeState::Err(_) => return,
+            _ => (),
+        }
+        self.state = IntcodeState::Running;
+        self.run_one();
+        if self.state == IntcodeState::Running {
+            self.state = IntcodeState::Ready;
+        }
+    }
+
+    /// Adds a breakpoint.  start() will pause with a Breakpoint state
+    /// whenever the computer's head reaches the given address, before the
+    /// instruction there is executed.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![1101,2,3,0,1102,2,3,4,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.add_breakpoint(4);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Breakpoint(4));
+    /// assert_eq!(comp.get(0), 5);
+    /// assert_eq!(comp.get(4), 1102);
+    /// 
+    /// // Resuming carries on from the breakpoint.
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(comp.get(4), 6);
+    /// 
+    /// // A breakpoint on an input instruction isn't hit again when the
+    /// // computer resumes after waiting for input there.
+    /// let tape: Vec<i64> = vec![3,5,4,5,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.add_breakpoint(0);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Breakpoint(0));
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::NeedsInput);
+    /// comp.push_input(7);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(comp.pop_output(), Some(7));
+    /// ```
+    pub fn add_breakpoint(&mut self, addr: usize) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Removes a breakpoint.  Returns false if there was no breakpoint at the
+    /// given address.
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Removes every breakpoint.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Returns the addresses of all breakpoints, in ascending order.
+    pub fn breakpoints(&self) -> Vec<usize> {
+        let mut addrs: Vec<usize> = self.breakpoints.iter().cloned().collect();
+        addrs.sort();
+        addrs
+    }
+
+    /// Adds a watchpoint covering a range of addresses, and returns an id that
+    /// can be passed to remove_watchpoint().  Whenever an instruction reads or
+    /// writes (depending on the given access) a cell in the range, the
+    /// watchpoint's action is triggered.  Only accesses made through
+    /// position- or relative-mode parameters count: fetching instructions and
+    /// immediate parameters doesn't, and neither do get() calls from outside
+    /// the computer.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use intcode::{Access, IntcodeState, WatchAction, WatchEvent};
+    /// 
+    /// // Adds 5 to address 9, then copies address 9 to address 10.
+    /// let tape: Vec<i64> = vec![1001,9,5,9,1001,9,0,10,99,1,0];
+    /// 
+    /// // Pause as soon as address 9 is written.
+    /// let mut comp = intcode::IntcodeComp::new(tape.clone());
+    /// comp.add_watchpoint(9..10, Access::Write, WatchAction::Pause);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), IntcodeState::Watchpoint(WatchEvent {
+    ///     ip: 0, addr: 9, access: Access::Write, old: 1, new: 6 }));
+    /// comp.start();
+    /// assert_eq!(*comp.state(), IntcodeState::Finished);
+    /// 
+    /// // An instruction that reads and writes a watched cell pauses with the
+    /// // write.
+    /// let mut comp = intcode::IntcodeComp::new(tape.clone());
+    /// comp.add_watchpoint(9..10, Access::ReadWrite, WatchAction::Pause);
+    /// comp.start();
+    /// assert_eq!(*comp.state(), IntcodeState::Watchpoint(WatchEvent {
+    ///     ip: 0, addr: 9, access: Access::Write, old: 1, new: 6 }));
+    /// 
+    /// // Record every read of address 9 without stopping.
+    /// let seen = Rc::new(RefCell::new(Vec::new()));
+    /// let log = seen.clone();
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.add_watchpoint(9..10, Access::Read, WatchAction::Callback(Box::new(move |e| {
+    ///     log.borrow_mut().push((e.ip, e.old));
+    /// })));
+    /// comp.start();
+    /// assert_eq!(*comp.state(), IntcodeState::Finished);
+    /// assert_eq!(*seen.borrow(), vec![(0, 1), (4, 6)]);
+    /// ```
+    pub fn add_watchpoint(&mut self, range: Range<usize>, access: Access, action: WatchAction<W>) -> usize {
+        let id = self.next_watch_id;
+        self.next_watch_id += 1;
+        self.watchpoints.push(Watchpoint { id, range, access, action });
+        id
+    }
+
+    /// Removes the watchpoint with the given id.  Returns false if there was
+    /// no such watchpoint.
+    pub fn remove_watchpoint(&mut self, id: usize) -> bool {
+        let before = self.watchpoints.len();
+        self.watchpoints.retain(|w| w.id != id);
+        self.watchpoints.len() != before
+    }
+
+    /// Returns the id, address range and access of every watchpoint, in the
+    /// order they were added.
+    pub fn watchpoints(&self) -> Vec<(usize, Range<usize>, Access)> {
+        self.watchpoints.iter().map(|w| (w.id, w.range.clone(), w.access)).collect()
+    }
+
+    /// Takes a snapshot of the computer.  The snapshot can later be passed to
+    /// restore() to rewind the computer to this point, so a caller exploring
+    /// several possible inputs can branch from a common state instead of
+    /// replaying everything leading up to it.  Breakpoints, watchpoints, the
+    /// tracer and attached I/O aren't part of the snapshot; only the input and
+    /// output queues are.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// // This tape outputs double whatever it's given.
+    /// let tape: Vec<i64> = vec![3,9,1002,9,2,9,4,9,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.start();
+    /// let waiting = comp.snapshot();
+    /// 
+    /// comp.push_input(5);
+    /// comp.start();
+    /// assert_eq!(comp.pop_output(), Some(10));
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// 
+    /// // Rewind and try again with a different input.
+    /// comp.restore(&waiting);
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::NeedsInput);
+    /// comp.push_input(7);
+    /// comp.start();
+    /// assert_eq!(comp.pop_output(), Some(14));
+    /// ```
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot {
+            tape: self.tape.clone(),
+            head: self.head,
+            inst_head: self.inst_head,
+            inst: self.inst.clone(),
+            mode: self.mode,
+            rel_base: self.rel_base,
+            max_memory: self.max_memory,
+            overflow: self.overflow,
+            state: self.state.clone(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    /// Rewinds (or fast-forwards) the computer to a snapshot.  The tape's
+    /// existing allocation is reused where possible.  Breakpoints, watchpoints,
+    /// the tracer and attached I/O are left as they are.
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        self.copy_program();
+        self.tape.clone_from(&snapshot.tape);
+        self.head = snapshot.head;
+        self.inst_head = snapshot.inst_head;
+        self.inst = snapshot.inst.clone();
+        self.mode = snapshot.mode;
+        self.rel_base = snapshot.rel_base;
+        self.max_memory = snapshot.max_memory;
+        self.overflow = snapshot.overflow;
+        self.state = snapshot.state.clone();
+        self.inputs.clone_from(&snapshot.inputs);
+        self.outputs.clone_from(&snapshot.outputs);
+        self.watch_hit = None;
+        self.decoded.clear();
+    }
+
+    /// Puts the computer back the way it was created: the original tape, with
+    /// the head, relative base and state at their starting values and both
+    /// queues empty.  The tape's allocation is reused, so a brute-force search
+    /// can reset one computer rather than build a new one for every attempt.
+    /// Patches made since the computer was created are undone too.
+    /// Breakpoints, watchpoints, the tracer, attached I/O and the computer's
+    /// settings are left as they are.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// // This tape outputs the product of cells 1 and 2.
+    /// let tape: Vec<i64> = vec![1102,0,0,7,4,7,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// let mut products = Vec::new();
+    /// for x in 1..4 {
+    ///     comp.reset();
+    ///     comp.patch(&[(1, x), (2, x)]).unwrap();
+    ///     comp.start();
+    ///     products.push(comp.pop_output().unwrap());
+    /// }
+    /// assert_eq!(products, vec![1, 4, 9]);
+    /// 
+    /// // This tape counts cell 9 down to zero, writing it more times than
+    /// // the tape has cells.
+    /// let tape: Vec<i64> = vec![1001,9,-1,9,1005,9,0,99,0,20];
+    /// let mut comp = intcode::IntcodeComp::new(tape.clone());
+    /// for _ in 0..2 {
+    ///     comp.start();
+    ///     assert_eq!(comp.get(9), 0);
+    ///     comp.reset();
+    ///     assert_eq!(comp.memory().iter().collect::<Vec<i64>>(), tape);
+    /// }
+    /// ```
+    pub fn reset(&mut self) {
+        match (&mut self.tape, &mut self.program) {
+            // Undo each change, latest first, so the earliest value of a cell
+            // written more than once ends up back in it.
+            (&mut Memory::Dense(ref mut cells), &mut Program::Changes(len, ref mut changes)) => {
+                for (i, old) in changes.drain(..).rev() {
+                    cells[i] = old;
+                    if let Some(slot) = self.decoded.get_mut(i) {
+                        *slot = None;
+                    }
+                }
+                cells.truncate(len);
+                self.decoded.truncate(len);
+            },
+            // Only copy back the cells that changed, so decoded instructions
+            // that are still valid stay cached for the next run.
+            (&mut Memory::Dense(ref mut cells), &mut Program::Copied(Memory::Dense(ref program))) => {
+                cells.truncate(program.len());
+                for (i, (cell, original)) in cells.iter_mut().zip(program).enumerate() {
+                    if *cell != *original {
+                        *cell = original.clone();
+                        if let Some(slot) = self.decoded.get_mut(i) {
+                            *slot = None;
+                        }
+                    }
+                }
+                let len = cells.len();
+                cells.extend_from_slice(&program[len..]);
+                self.decoded.truncate(len);
+            },
+            (tape, &mut Program::Copied(ref program)) => {
+                tape.clone_from(program);
+                self.decoded.clear();
+            },
+            (&mut Memory::Sparse(_), &mut Program::Changes(..)) =>
+                unreachable!("changes are only kept for dense tapes"),
+        }
+        self.head = 0;
+        self.inst_head = 0;
+        self.inst = W::from_i64(0);
+        self.mode = [Mode::Position; 3];
+        self.rel_base = 0;
+        self.state = IntcodeState::Ready;
+        self.inputs.clear();
+        self.outputs.clear();
+        self.watch_hit = None;
+    }
+
+    /// Overwrites some cells of the tape, given as (address, value) pairs.
+    /// Like set(), this doesn't trigger watchpoints.  If any address is at or
+    /// beyond the memory limit, an AddressOutOfRange error is returned and
+    /// nothing is written.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![1,0,0,0,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape).with_max_memory(100);
+    /// assert!(comp.patch(&[(1, 4), (100, 4)]).is_err());
+    /// assert_eq!(comp.get(1), 0);
+    /// comp.patch(&[(1, 4), (2, 4)]).unwrap();
+    /// assert_eq!(comp.get(1), 4);
+    /// ```
+    pub fn patch(&mut self, patches: &[(usize, W)]) -> Result<(), IntcodeError<W>> {
+        for &(addr, _) in patches {
+            self.check_index(addr)?;
+        }
+        for &(addr, ref value) in patches {
+            self.set(addr, value.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Returns an independent copy of the computer, which carries on from
+    /// exactly the same point.  Breakpoints and the overflow policy are copied
+    /// too, but watchpoints, the tracer, and any attached input source or
+    /// output destination are not.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![3,9,1002,9,2,9,4,9,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.start();
+    /// let mut other = comp.fork();
+    /// comp.push_input(5);
+    /// comp.start();
+    /// other.push_input(6);
+    /// other.start();
+    /// assert_eq!(comp.pop_output(), Some(10));
+    /// assert_eq!(other.pop_output(), Some(12));
+    /// ```
+    pub fn fork(&self) -> IntcodeComp<W> {
+        let mut comp = IntcodeComp::new(Vec::new());
+        comp.restore(&self.snapshot());
+        comp.program = self.program.clone();
+        comp.overflow = self.overflow;
+        comp.breakpoints = self.breakpoints.clone();
+        comp
+    }
+
+    /// Starts tracing every instruction the computer executes, replacing any
+    /// previous tracer.  See the trace module for the format of each line.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use std::cell::RefCell;
+    /// use std::io;
+    /// use std::rc::Rc;
+    /// use intcode::trace::{TraceFormat, Tracer};
+    /// 
+    /// // A sink we can still read after handing it to the tracer.
+    /// #[derive(Clone)]
+    /// struct Shared(Rc<RefCell<Vec<u8>>>);
+    /// impl io::Write for Shared {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(buf) }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    /// 
+    /// let tape: Vec<i64> = vec![3,9,1002,9,3,9,4,9,99,0];
+    /// let buf = Shared(Rc::new(RefCell::new(Vec::new())));
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.set_tracer(Tracer::new(buf.clone(), TraceFormat::Text));
+    /// comp.push_input(11);
+    /// comp.start();
+    /// assert_eq!(String::from_utf8(buf.0.borrow().clone()).unwrap(), "\
+    /// 0000 IN 9 [9]=11 rb=0
+    /// 0002 MUL 11,3,9 [9]=33 rb=0
+    /// 0006 OUT 33 - rb=0
+    /// 0008 HLT - - rb=0
+    /// ");
+    /// 
+    /// // The instruction that fails is traced too, along with the error.
+    /// let tape: Vec<i64> = vec![1101,2,3,9,204,-1,42];
+    /// let buf = Shared(Rc::new(RefCell::new(Vec::new())));
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.set_tracer(Tracer::new(buf.clone(), TraceFormat::Json));
+    /// comp.start();
+    /// assert_eq!(String::from_utf8(buf.0.borrow().clone()).unwrap(), concat!(
+    ///     r#"{"head":0,"op":"ADD","operands":[2,3,9],"write":{"addr":9,"value":5},"rel_base":0}"#, "\n",
+    ///     r#"{"head":4,"op":"OUT","operands":[],"write":null,"rel_base":0,"error":"#,
+    ///     r#""negative address -1 (head 4, instruction 204, relative base 0)"}"#, "\n"));
+    /// ```
+    pub fn set_tracer(&mut self, tracer: Tracer<W>) {
+        self.tracer = Some(tracer);
+    }
+
+    /// Stops tracing, and returns the tracer that was in use (if any).
+    pub fn take_tracer(&mut self) -> Option<Tracer<W>> {
+        self.tracer.take()
+    }
+
+    /// Returns the position of the next instruction to be executed.
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    /// Returns the current relative base.
+    pub fn rel_base(&self) -> i64 {
+        self.rel_base
+    }
+
+    /// Returns the whole tape as it currently stands.  Cells at or past its
+    /// len() have never been written, and read as zero.
+    pub fn memory(&self) -> &Memory<W> {
+        &self.tape
+    }
+
+    /// Helper function that executes the next instruction, moving the
+    /// computer into the Err state if it fails.
+    fn run_one(&mut self) {
+        match self.execute_one() {
+            Ok(()) => {
+                // Starving for input rewinds the instruction rather than
+                // executing it, so there's nothing to trace.
+                if let Some(ref mut t) = self.tracer {
+                    if self.state == IntcodeState::NeedsInput {
+                        t.cancel();
+                    } else {
+                        t.finish(self.rel_base);
+                    }
+                }
+                if let Some(event) = self.watch_hit.take() {
+                    if self.state == IntcodeState::Running {
+                        self.state = IntcodeState::Watchpoint(event);
+                    }
+                }
+            },
+            Err(e) => {
+                if let Some(ref mut t) = self.tracer {
+                    t.fail(self.inst_head, self.rel_base, &e);
+                }
+                self.watch_hit = None;
+                self.state = IntcodeState::Err(e);
+            },
+        }
+    }
+
+    /// Helper function that triggers any watchpoints covering a memory access
+    /// made by the current instruction.
+    fn watch(&mut self, addr: usize, access: Access, old: W, new: W) {
+        let event = WatchEvent { ip: self.inst_head, addr, access, old, new };
+        let mut pause = false;
+        for w in self.watchpoints.iter_mut() {
+            if !w.range.contains(&addr) || (w.access != access && w.access != Access::ReadWrite) {
+                continue;
+            }
+            match w.action {
+                WatchAction::Pause => pause = true,
+                WatchAction::Callback(ref mut f) => f(&event),
+            }
+        }
+        // An instruction writes after its reads, so a write replaces any read
+        // seen so far: it's the access that changed something.
+        if pause && (self.watch_hit.is_none() || access == Access::Write) {
+            self.watch_hit = Some(event);
         }
-        self.state = IntcodeState::Finished;
     }
 
     /// Given an index, return the value in the corresponding cell on the
@@ -159,22 +1690,98 @@ impl IntcodeComp {
     /// assert_eq!(comp.get(0), 5);
     /// assert_eq!(comp.get(4), 6);
     /// ```
-    pub fn get(&mut self, i: usize) -> i64 {
-        if i >= self.tape.len() {
-            return 0;
+    #[inline(always)]
+    pub fn get(&mut self, i: usize) -> W {
+        self.tape.get(i)
+    }
+
+    /// Given an index, set the value in the corresponding cell on the
+    /// computer's tape.  If the given position is beyond the tape's bounds,
+    /// this function will allocate additional memory at the end of the tape
+    /// so make the tape large enough to include the given position.  Like an
+    /// instruction's write, a position at or beyond the memory limit is
+    /// rejected with an AddressOutOfRange error.  This is meant for patching
+    /// a tape from outside the computer, so it doesn't trigger watchpoints.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let tape: Vec<i64> = vec![1,0,0,0,99];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.set(1, 4).unwrap();
+    /// comp.set(2, 4).unwrap();
+    /// assert!(comp.set(100_000_000_000_000, 1).is_err());
+    /// comp.start();
+    /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
+    /// assert_eq!(comp.get(0), 198);
+    /// ```
+    pub fn set(&mut self, i: usize, v: W) -> Result<(), IntcodeError<W>> {
+        self.check_index(i)?;
+        self.invalidate(i);
+        let old = self.tape.set(i, v);
+        self.changed(i, old);
+        Ok(())
+    }
+
+    /// Helper function that notes a change to a cell, so reset() can undo
+    /// it.
+    #[inline(always)]
+    fn changed(&mut self, i: usize, old: W) {
+        if let Memory::Dense(ref cells) = self.tape {
+            self.program.record(cells, i, old);
         }
-        self.tape[i]
     }
 
-    /// Set the value at a given index on the tape.  If the given position is
-    /// beyond the tape's bounds, this function will allocate additional memory
-    /// at the end of the tape so make the tape large enough to include the
-    /// given position.
-    fn set(&mut self, i: usize, v: i64) {
-        if i >= self.tape.len() {
-            self.tape.resize(i+1, 0);
+    /// Helper function that makes sure the computer holds a copy of the tape
+    /// it started with, before its tape is replaced or changes kind.
+    fn copy_program(&mut self) {
+        if let Memory::Dense(ref cells) = self.tape {
+            self.program.copy(cells);
         }
-        self.tape[i] = v;
+    }
+
+    /// Helper function that forgets the decoded instruction at an address,
+    /// since its cell has changed.
+    #[inline(always)]
+    fn invalidate(&mut self, i: usize) {
+        if let Some(slot) = self.decoded.get_mut(i) {
+            *slot = None;
+        }
+    }
+
+    /// Write a value to an address computed by an instruction.  Like set(),
+    /// the tape grows to include the given position.  Negative addresses, and
+    /// addresses beyond the computer's memory limit, are rejected with an
+    /// error.
+    #[inline(always)]
+    fn write(&mut self, pos: W, v: W) -> Result<(), IntcodeError<W>> {
+        let i = self.check_address(&pos)?;
+        self.invalidate(i);
+        if let Some(ref mut t) = self.tracer {
+            t.write(i, v.clone());
+        }
+        if self.watchpoints.is_empty() {
+            let old = self.tape.set(i, v);
+            self.changed(i, old);
+            return Ok(());
+        }
+        let old = self.tape.set(i, v.clone());
+        self.changed(i, old.clone());
+        self.watch(i, Access::Write, old, v);
+        Ok(())
+    }
+
+    /// Read the value at an address computed by an instruction.  Like
+    /// write(), negative addresses and addresses beyond the computer's memory
+    /// limit are rejected with an error.
+    #[inline(always)]
+    fn read(&mut self, pos: W) -> Result<W, IntcodeError<W>> {
+        let i = self.check_address(&pos)?;
+        let v = self.get(i);
+        if !self.watchpoints.is_empty() {
+            self.watch(i, Access::Read, v.clone(), v.clone());
+        }
+        Ok(v)
     }
 
     /// Push input into the computer's input queue.  This function can be used
@@ -211,7 +1818,7 @@ impl IntcodeComp {
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// assert_eq!(comp.get(9), 15);
     /// ```
-    pub fn push_input(&mut self, i: i64) {
+    pub fn push_input(&mut self, i: W) {
         self.inputs.push_back(i);
     }
 
@@ -238,69 +1845,186 @@ impl IntcodeComp {
     /// assert_eq!(comp.get(13), 11);
     /// assert_eq!(comp.get(14), 30);
     /// ```
-    pub fn pop_output(&mut self) -> Option<i64> {
+    pub fn pop_output(&mut self) -> Option<W> {
         self.outputs.pop_front()
     }
 
     /// Returns the current state of the computer.
-    pub fn state(&self) -> &IntcodeState {
+    pub fn state(&self) -> &IntcodeState<W> {
         &self.state
     }
 
     /// Private function used to read and execute the tape's next instruction.
-    fn execute_one(&mut self) {
-        let op = self.get(self.head) % 100;
-        self.mode = self.get(self.head) / 100;
+    fn execute_one(&mut self) -> Result<(), IntcodeError<W>> {
+        self.executed += 1;
+        self.inst_head = self.head;
+        self.inst = self.get(self.head);
+        let inst = match self.decoded.get(self.head) {
+            Some(&Some(inst)) => inst,
+            _ => self.decode()?,
+        };
+        self.mode = inst.modes;
         self.head += 1;
-        match op {
-            1 => self.add(),
-            2 => self.mult(),
-            3 => self.input(),
-            4 => self.output(),
-            5 => self.jump_if_true(),
-            6 => self.jump_if_false(),
-            7 => self.less_than(),
-            8 => self.equals(),
-            9 => self.rel_adjust(),
-            _ => panic!("Unsupported opcode!  Current computer state: opcode {}, head {}, mode {}",
-                        op, self.head, self.mode),
+        if let Some(ref mut t) = self.tracer {
+            t.begin(self.inst_head, inst.opcode);
+        }
+        match inst.opcode {
+            Opcode::Add => self.add(),
+            Opcode::Mult => self.mult(),
+            Opcode::Input => self.input(),
+            Opcode::Output => self.output(),
+            Opcode::JumpIfTrue => self.jump_if_true(),
+            Opcode::JumpIfFalse => self.jump_if_false(),
+            Opcode::LessThan => self.less_than(),
+            Opcode::Equals => self.equals(),
+            Opcode::RelAdjust => self.rel_adjust(),
+            Opcode::Halt => {
+                // Leave the head on the halt, so the finished computer still
+                // points at the instruction that stopped it.
+                self.head = self.inst_head;
+                self.state = IntcodeState::Finished;
+                Ok(())
+            },
+        }
+    }
+
+    /// Helper function that decodes the current instruction, caching it if
+    /// its address is low enough and the computer has run for long enough.
+    fn decode(&mut self) -> Result<Instruction, IntcodeError<W>> {
+        let decoded = match self.inst.to_i64() {
+            Some(value) => Instruction::decode(value),
+            None => Err(DecodeError::UnknownOpcode),
+        };
+        let inst = match decoded {
+            Ok(inst) => inst,
+            Err(e) => return Err(self.decode_error(e)),
+        };
+        if self.inst_head < DECODE_CACHE_CELLS && self.executed >= DECODE_CACHE_AFTER {
+            if self.inst_head >= self.decoded.len() {
+                self.decoded.resize(cmp::max(self.inst_head + 1, self.tape.len()).min(DECODE_CACHE_CELLS), None);
+            }
+            self.decoded[self.inst_head] = Some(inst);
+        }
+        Ok(inst)
+    }
+
+    /// Helper function that attaches the current instruction's details to a
+    /// decoding failure.
+    fn decode_error(&self, e: DecodeError) -> IntcodeError<W> {
+        match e {
+            DecodeError::UnknownOpcode => IntcodeError::UnknownOpcode {
+                head: self.inst_head,
+                instruction: self.inst.clone(),
+                rel_base: self.rel_base,
+            },
+            DecodeError::UnknownMode(m) => IntcodeError::UnknownMode {
+                head: self.inst_head,
+                instruction: self.inst.clone(),
+                rel_base: self.rel_base,
+                mode: m,
+            },
+            DecodeError::ImmediateWrite => IntcodeError::ImmediateWrite {
+                head: self.inst_head,
+                instruction: self.inst.clone(),
+                rel_base: self.rel_base,
+            },
+        }
+    }
+
+    /// Helper function that describes an overflow combining two values in the
+    /// current instruction.
+    fn overflow_error(&self, x: W, y: W) -> IntcodeError<W> {
+        IntcodeError::Overflow {
+            head: self.inst_head,
+            instruction: self.inst.clone(),
+            rel_base: self.rel_base,
+            operands: (x, y),
         }
     }
 
     /// Helper function that reads a parameter pointed to by the computer's
     /// head, and returns its value based on the current parameter mode.
-    fn get_param(&mut self, is_output: bool) -> i64 {
-        let m = self.mode % 10;
-        self.mode /= 10;
-        match m {
+    /// Output parameters return the position to write to instead.
+    /// 
+    /// Every parameter of every instruction passes through here, so this and
+    /// the helpers it calls are always inlined.
+    #[inline(always)]
+    fn get_param(&mut self, is_output: bool) -> Result<W, IntcodeError<W>> {
+        let m = self.mode[self.head - self.inst_head - 1];
+        let pos = match m {
             // Positional: get the value from the specified position.
-            0 => {
-                let pos = self.get(self.head);
-                self.head += 1;
-                if is_output { return pos; }
-                return self.get(pos as usize);
-            },
-            // Immediate: use this value directly.
-            1 => {
+            Mode::Position => self.get(self.head),
+            // Immediate: use this value directly.  Instruction::decode()
+            // already rejects immediate output parameters.
+            Mode::Immediate => {
                 if is_output {
-                    panic!("Mode 1 not supported for output params!  Current \
-                            computer state: m {}, head {}, mode {}",
-                            m, self.head, self.mode)
+                    return Err(self.decode_error(DecodeError::ImmediateWrite));
                 }
                 let val = self.get(self.head);
                 self.head += 1;
-                return val;
+                self.trace_operand(&val);
+                return Ok(val);
             },
             // Relative: add this value to self.rel_base and use the value at
-            // that position.
-            2 => {
-                let pos = self.rel_base + self.get(self.head);
-                self.head += 1;
-                if is_output { return pos; }
-                return self.get(pos as usize);
+            // that position.  A sum too big for a word is out of range
+            // whichever way it went, and the offset shows which way.
+            Mode::Relative => {
+                let offset = self.get(self.head);
+                match W::from_i64(self.rel_base).checked_add(&offset) {
+                    Some(pos) => pos,
+                    None => return Err(self.address_error(offset)),
+                }
+            },
+        };
+        self.head += 1;
+        let val = if is_output { pos } else { self.read(pos)? };
+        self.trace_operand(&val);
+        Ok(val)
+    }
+
+    /// Helper function that passes a resolved parameter to the tracer, if
+    /// there is one.
+    #[inline(always)]
+    fn trace_operand(&mut self, val: &W) {
+        if let Some(ref mut t) = self.tracer {
+            t.operand(val.clone());
+        }
+    }
+
+    /// Helper function that converts a computed address into a tape index,
+    /// failing if the address is negative or beyond the memory limit.
+    #[inline(always)]
+    fn check_address(&self, pos: &W) -> Result<usize, IntcodeError<W>> {
+        if let Some(i) = pos.to_i64() {
+            if i >= 0 && (i as u64) < self.max_memory as u64 {
+                return Ok(i as usize);
             }
-            _ => panic!("Unsupported mode!  Current computer state: m {}, head {}, mode {}",
-                        m, self.head, self.mode),
+        }
+        Err(self.address_error(pos.clone()))
+    }
+
+    /// Helper function that checks an address given from outside the
+    /// computer against its memory limit, like check_address().
+    fn check_index(&self, i: usize) -> Result<usize, IntcodeError<W>> {
+        self.check_address(&W::from_i64(cmp::min(i, i64::MAX as usize) as i64))
+    }
+
+    /// Helper function that describes an address check_address() rejected.
+    fn address_error(&self, pos: W) -> IntcodeError<W> {
+        if pos < W::from_i64(0) {
+            return IntcodeError::NegativeAddress {
+                head: self.inst_head,
+                instruction: self.inst.clone(),
+                rel_base: self.rel_base,
+                address: pos,
+            };
+        }
+        IntcodeError::AddressOutOfRange {
+            head: self.inst_head,
+            instruction: self.inst.clone(),
+            rel_base: self.rel_base,
+            address: pos,
+            limit: self.max_memory,
         }
     }
 
@@ -315,11 +2039,20 @@ impl IntcodeComp {
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// assert_eq!(comp.get(4), 99);
     /// ```
-    fn add(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        let pos: usize = self.get_param(true) as usize;
-        self.set(pos, x + y);
+    fn add(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        let pos = self.get_param(true)?;
+        let sum = match self.overflow {
+            OverflowPolicy::Wrap => x.wrapping_add(&y),
+            OverflowPolicy::Saturate => x.saturating_add(&y),
+            OverflowPolicy::Checked => match x.checked_add(&y) {
+                Some(sum) => sum,
+                None => return Err(self.overflow_error(x, y)),
+            },
+        };
+        self.write(pos, sum)?;
+        Ok(())
     }
 
     /// Implementation of the multiply operation.
@@ -333,11 +2066,20 @@ impl IntcodeComp {
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// assert_eq!(comp.get(4), 99);
     /// ```
-    fn mult(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        let pos: usize = self.get_param(true) as usize;
-        self.set(pos, x * y);
+    fn mult(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        let pos = self.get_param(true)?;
+        let product = match self.overflow {
+            OverflowPolicy::Wrap => x.wrapping_mul(&y),
+            OverflowPolicy::Saturate => x.saturating_mul(&y),
+            OverflowPolicy::Checked => match x.checked_mul(&y) {
+                Some(product) => product,
+                None => return Err(self.overflow_error(x, y)),
+            },
+        };
+        self.write(pos, product)?;
+        Ok(())
     }
 
     /// Implementation of the input operation.
@@ -353,15 +2095,24 @@ impl IntcodeComp {
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// assert_eq!(comp.get(2), 99);
     /// ```
-    fn input(&mut self) {
-        let maybe_input = self.inputs.pop_front();
+    fn input(&mut self) -> Result<(), IntcodeError<W>> {
+        let maybe_input = match self.inputs.pop_front() {
+            Some(input) => Some(input),
+            None => self.input_source.as_mut().and_then(|s| s.next_input()),
+        };
         match maybe_input {
             Some(input) => {
-                let pos: usize = self.get_param(true) as usize;
-                self.set(pos, input);
+                let pos = self.get_param(true)?;
+                self.write(pos, input)?;
+            },
+            None => {
+                // Rewind to the start of the instruction, so it's executed
+                // from scratch once input is available.
+                self.head = self.inst_head;
+                self.state = IntcodeState::NeedsInput;
             },
-            None => self.state = IntcodeState::NeedsInput,
         }
+        Ok(())
     }
 
     /// Implementation of the output operation.
@@ -375,9 +2126,13 @@ impl IntcodeComp {
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// assert_eq!(comp.pop_output(), Some(50));
     /// ```
-    fn output(&mut self) {
-        let out = self.get_param(false);
-        self.outputs.push_back(out);
+    fn output(&mut self) -> Result<(), IntcodeError<W>> {
+        let out = self.get_param(false)?;
+        match self.output_sink {
+            Some(ref mut sink) => sink.write_output(out),
+            None => self.outputs.push_back(out),
+        }
+        Ok(())
     }
 
     /// Implementation of the jump-if-true operation.
@@ -390,12 +2145,13 @@ impl IntcodeComp {
     /// comp.start();
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// ```
-    fn jump_if_true(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        if x != 0 {
-            self.head = y as usize;
+    fn jump_if_true(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        if x != W::from_i64(0) {
+            self.head = self.check_address(&y)?;
         }
+        Ok(())
     }
 
     /// Implementation of the jump-if-false operation.
@@ -408,12 +2164,13 @@ impl IntcodeComp {
     /// comp.start();
     /// assert_eq!(*comp.state(), intcode::IntcodeState::Finished);
     /// ```
-    fn jump_if_false(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        if x == 0 {
-            self.head = y as usize;
+    fn jump_if_false(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        if x == W::from_i64(0) {
+            self.head = self.check_address(&y)?;
         }
+        Ok(())
     }
 
     /// Implementation of the less-than operation.
@@ -428,15 +2185,16 @@ impl IntcodeComp {
     /// assert_eq!(comp.get(0), 1);
     /// assert_eq!(comp.get(4), 0);
     /// ```
-    fn less_than(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        let pos: usize = self.get_param(true) as usize;
+    fn less_than(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        let pos = self.get_param(true)?;
         if x < y {
-            self.set(pos, 1);
+            self.write(pos, W::from_i64(1))?;
         } else {
-            self.set(pos, 0);
+            self.write(pos, W::from_i64(0))?;
         }
+        Ok(())
     }
 
     /// Implementation of the equals operation.
@@ -451,15 +2209,16 @@ impl IntcodeComp {
     /// assert_eq!(comp.get(0), 0);
     /// assert_eq!(comp.get(4), 1);
     /// ```
-    fn equals(&mut self) {
-        let x = self.get_param(false);
-        let y = self.get_param(false);
-        let pos: usize = self.get_param(true) as usize;
+    fn equals(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        let y = self.get_param(false)?;
+        let pos = self.get_param(true)?;
         if x == y {
-            self.set(pos, 1);
+            self.write(pos, W::from_i64(1))?;
         } else {
-            self.set(pos, 0);
+            self.write(pos, W::from_i64(0))?;
         }
+        Ok(())
     }
 
     /// Implementation of the relative-adjust operation.
@@ -478,14 +2237,20 @@ impl IntcodeComp {
     /// assert_eq!(comp.get(6), 6);
     /// assert_eq!(comp.get(12), 4);
     /// ```
-    fn rel_adjust(&mut self) {
-        let x = self.get_param(false);
-        self.rel_base += x;
+    fn rel_adjust(&mut self) -> Result<(), IntcodeError<W>> {
+        let x = self.get_param(false)?;
+        match x.to_i64().and_then(|x| self.rel_base.checked_add(x)) {
+            Some(rel_base) => self.rel_base = rel_base,
+            None => return Err(self.overflow_error(W::from_i64(self.rel_base), x)),
+        }
+        Ok(())
     }
 }
 
 /// Helper function to help convert a comma-delimited string of integers into
-/// a vector of integers (to be passed to an IntcodeComp as tape).
+/// a vector of integers (to be passed to an IntcodeComp as tape).  This
+/// panics if the string isn't a valid tape; use parse_tape() to handle the
+/// error instead.
 /// 
 /// # Example
 /// 
@@ -498,5 +2263,8 @@ impl IntcodeComp {
 /// assert_eq!(comp.get(0), 4);
 /// ```
 pub fn to_tape(line: &str) -> Vec<i64> {
-    line.split(",").map(|p| p.parse::<i64>().unwrap()).collect()
+    match parse_tape(line) {
+        Ok(tape) => tape,
+        Err(e) => panic!("{}", e),
+    }
 }
\ No newline at end of file
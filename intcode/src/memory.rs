@@ -0,0 +1,188 @@
+//! How a computer's memory is stored.
+//!
+//! Memory is either dense or sparse, chosen with IntcodeComp::with_memory().
+//! Dense memory is a plain Vec, which grows to cover the highest address
+//! written; it's the fastest choice for tapes that stay near their initial
+//! size.  Sparse memory is split into pages of PAGE_SIZE cells, and only pages
+//! that hold a non-zero value are allocated, so tapes that scatter writes
+//! across a huge address range still run in bounded memory.
+
+use std::collections::HashMap;
+use std::mem;
+
+use super::Word;
+
+/// The number of cells in each page of sparse memory.
+pub const PAGE_SIZE: usize = 1024;
+
+/// The ways a computer's memory can be stored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MemoryKind {
+    /// A single Vec covering every address up to the highest written.  This
+    /// is the default.
+    #[default]
+    Dense,
+    /// Pages allocated only where non-zero values are written.
+    Sparse,
+}
+
+/// A computer's memory.  Every cell reads as zero until it's written.
+#[derive(Debug, PartialEq)]
+pub enum Memory<W = i64> {
+    /// Every cell up to the highest written, in order.
+    Dense(Vec<W>),
+    /// Only the pages that have been written to.
+    Sparse(SparseMemory<W>),
+}
+
+/// The pages of a sparse memory, see the module documentation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMemory<W> {
+    /// Allocated pages, keyed by their number (address / PAGE_SIZE).
+    pages: HashMap<usize, Box<[W]>>,
+    /// One past the highest address written.
+    len: usize,
+}
+
+/// Reuses the existing allocation when both sides are dense, so a computer
+/// can be rewound to a snapshot without reallocating its tape.
+impl<W: Clone> Clone for Memory<W> {
+    fn clone(&self) -> Memory<W> {
+        match *self {
+            Memory::Dense(ref cells) => Memory::Dense(cells.clone()),
+            Memory::Sparse(ref sparse) => Memory::Sparse(sparse.clone()),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Memory<W>) {
+        match (self, source) {
+            (&mut Memory::Dense(ref mut cells), Memory::Dense(other)) => cells.clone_from(other),
+            (this, source) => *this = source.clone(),
+        }
+    }
+}
+
+impl<W: Word> Memory<W> {
+    /// Creates a memory of the given kind holding a tape.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use intcode::memory::{Memory, MemoryKind};
+    ///
+    /// let mut mem: Memory = Memory::new(MemoryKind::Sparse, vec![1,2,3]);
+    /// assert_eq!(mem.len(), 3);
+    /// assert_eq!(mem.get(1), 2);
+    /// assert_eq!(mem.set(1_000_000_000, 7), 0);
+    /// assert_eq!(mem.get(1_000_000_000), 7);
+    /// assert_eq!(mem.len(), 1_000_000_001);
+    /// assert_eq!(mem.allocated(), 2 * intcode::memory::PAGE_SIZE);
+    /// ```
+    pub fn new(kind: MemoryKind, tape: Vec<W>) -> Memory<W> {
+        match kind {
+            MemoryKind::Dense => Memory::Dense(tape),
+            MemoryKind::Sparse => {
+                let mut sparse = SparseMemory { pages: HashMap::new(), len: 0 };
+                for (i, v) in tape.into_iter().enumerate() {
+                    sparse.set(i, v);
+                }
+                Memory::Sparse(sparse)
+            },
+        }
+    }
+
+    /// Returns how the memory is stored.
+    pub fn kind(&self) -> MemoryKind {
+        match *self {
+            Memory::Dense(_) => MemoryKind::Dense,
+            Memory::Sparse(_) => MemoryKind::Sparse,
+        }
+    }
+
+    /// Returns one past the highest address that has been written (or loaded
+    /// with the initial tape).
+    pub fn len(&self) -> usize {
+        match *self {
+            Memory::Dense(ref cells) => cells.len(),
+            Memory::Sparse(ref sparse) => sparse.len,
+        }
+    }
+
+    /// Returns true if nothing has been written.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of cells actually allocated.
+    pub fn allocated(&self) -> usize {
+        match *self {
+            Memory::Dense(ref cells) => cells.len(),
+            Memory::Sparse(ref sparse) => sparse.pages.len() * PAGE_SIZE,
+        }
+    }
+
+    /// Returns the value at an address.
+    pub fn get(&self, i: usize) -> W {
+        let cell = match *self {
+            Memory::Dense(ref cells) => cells.get(i),
+            Memory::Sparse(ref sparse) => sparse.pages.get(&(i / PAGE_SIZE)).map(|p| &p[i % PAGE_SIZE]),
+        };
+        match cell {
+            Some(v) => v.clone(),
+            None => W::from_i64(0),
+        }
+    }
+
+    /// Sets the value at an address, and returns the value it replaced.
+    pub fn set(&mut self, i: usize, v: W) -> W {
+        match *self {
+            Memory::Dense(ref mut cells) => {
+                if i >= cells.len() {
+                    cells.resize(i+1, W::from_i64(0));
+                }
+                mem::replace(&mut cells[i], v)
+            },
+            Memory::Sparse(ref mut sparse) => sparse.set(i, v),
+        }
+    }
+
+    /// Returns every value from address zero up to len().
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = W> + 'a {
+        (0..self.len()).map(move |i| self.get(i))
+    }
+
+    /// Returns the address and value of every non-zero cell, in address order.
+    pub fn non_zero(&self) -> Vec<(usize, W)> {
+        let zero = W::from_i64(0);
+        let mut cells: Vec<(usize, W)> = match *self {
+            Memory::Dense(ref cells) => cells.iter().cloned().enumerate().collect(),
+            Memory::Sparse(ref sparse) => sparse.pages.iter()
+                .flat_map(|(n, page)| page.iter().cloned().enumerate().map(move |(i, v)| (n * PAGE_SIZE + i, v)))
+                .collect(),
+        };
+        cells.retain(|(_, v)| *v != zero);
+        cells.sort_by_key(|&(i, _)| i);
+        cells
+    }
+
+    /// Returns a copy of every value from address zero up to len().
+    pub fn to_vec(&self) -> Vec<W> {
+        match *self {
+            Memory::Dense(ref cells) => cells.clone(),
+            Memory::Sparse(_) => self.iter().collect(),
+        }
+    }
+}
+
+impl<W: Word> SparseMemory<W> {
+    fn set(&mut self, i: usize, v: W) -> W {
+        let zero = W::from_i64(0);
+        self.len = self.len.max(i + 1);
+        if v == zero && !self.pages.contains_key(&(i / PAGE_SIZE)) {
+            return zero;
+        }
+        let page = self.pages.entry(i / PAGE_SIZE)
+            .or_insert_with(|| vec![zero; PAGE_SIZE].into_boxed_slice());
+        mem::replace(&mut page[i % PAGE_SIZE], v)
+    }
+}
@@ -0,0 +1,269 @@
+//! A deterministic simulator for networks of computers.
+//!
+//! Every computer in a Network runs the same tape.  Each boots with its
+//! address (0 to N-1) as its first input, and then talks in packets: it sends
+//! one by outputting a destination address followed by two values x and y, and
+//! receives one as two inputs, x then y.  A computer that asks for input when
+//! no packet is waiting for it is given -1.
+//!
+//! The computers are run one at a time, in address order.  On its turn a
+//! computer is given the next packet in its queue (or -1), and runs until it
+//! asks for more input.  The packets it sent are then routed: a packet for an
+//! address on the network is queued for that computer, and any other packet is
+//! handed to the NAT hook passed to Network::run().
+//!
+//! The network is idle after a full round in which no computer received or
+//! sent a packet.  The NAT hook is told about it and can wake the network by
+//! sending a packet.  Since nothing depends on timing, every run of the same
+//! tape gives the same result.
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+
+use super::{IntcodeComp, IntcodeError, IntcodeState, Word};
+
+/// A packet sent over the network.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Packet<W = i64> {
+    /// The address the packet is for.
+    pub dest: W,
+    /// The first value carried.
+    pub x: W,
+    /// The second value carried.
+    pub y: W,
+}
+
+/// Something the NAT hook is told about.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetEvent<W = i64> {
+    /// A computer sent a packet to an address that isn't on the network.
+    Packet(Packet<W>),
+    /// The network is idle, see the module documentation.
+    Idle,
+}
+
+/// What the NAT hook wants to happen next.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NetAction<W = i64> {
+    /// Carry on.  Returned for an Idle event, this leaves the network idle,
+    /// so Network::run() returns NetOutcome::Idle.
+    Continue,
+    /// Queues a packet for the computer at its destination.
+    Send(Packet<W>),
+    /// Stops the network.  Network::run() returns NetOutcome::Stopped.
+    Stop,
+}
+
+/// Why Network::run() returned.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NetOutcome {
+    /// The NAT hook asked for the network to stop.
+    Stopped,
+    /// The network was idle, and the NAT hook didn't wake it.
+    Idle,
+    /// Every computer has halted.
+    Halted,
+}
+
+/// The ways running a network can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NetError<W = i64> {
+    /// A computer failed.  Its address and error are included.
+    Machine { addr: usize, error: IntcodeError<W> },
+    /// The NAT hook sent a packet to an address that isn't on the network.
+    /// The address is included.
+    UnknownAddress(W),
+    /// A computer halted partway through sending a packet.  Its address is
+    /// included.
+    TruncatedPacket { addr: usize },
+}
+
+impl<W: Word> fmt::Display for NetError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NetError::Machine { addr, ref error } => write!(f, "computer {} failed: {}", addr, error),
+            NetError::UnknownAddress(ref dest) => write!(f, "no computer at address {}", dest),
+            NetError::TruncatedPacket { addr } => write!(f, "computer {} halted partway through a packet", addr),
+        }
+    }
+}
+
+impl<W: Word> error::Error for NetError<W> {}
+
+/// A network of computers, see the module documentation.
+pub struct Network<W = i64> {
+    machines: Vec<IntcodeComp<W>>,
+    /// The packets waiting for each computer, as (x, y).
+    queues: Vec<VecDeque<(W, W)>>,
+    /// Output from each computer that doesn't make up a whole packet yet.
+    partial: Vec<Vec<W>>,
+    rounds: u64,
+}
+
+impl<W: Word> Network<W> {
+    /// Creates a network of the given number of computers, each running a
+    /// copy of the tape.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use intcode::net::{NetAction, NetEvent, NetOutcome, Network, Packet};
+    ///
+    /// // Each computer sends (its address, 0) to address 255 when it boots, and
+    /// // then echoes every x it receives back to 255.
+    /// let tape: Vec<i64> = vec![3,100,104,255,4,100,104,0,3,101,1008,101,-1,103,1005,103,8,
+    ///                           3,102,104,255,4,101,104,0,1105,1,8];
+    /// let mut network = Network::new(tape, 2);
+    /// let mut seen = Vec::new();
+    /// let mut woken = false;
+    /// let outcome = network.run(|event| match event {
+    ///     NetEvent::Packet(p) => {
+    ///         seen.push((p.x, p.y));
+    ///         NetAction::Continue
+    ///     },
+    ///     NetEvent::Idle if !woken => {
+    ///         woken = true;
+    ///         NetAction::Send(Packet { dest: 1, x: 7, y: 8 })
+    ///     },
+    ///     NetEvent::Idle => NetAction::Continue,
+    /// });
+    /// assert_eq!(outcome, Ok(NetOutcome::Idle));
+    /// assert_eq!(seen, vec![(0, 0), (1, 0), (7, 0)]);
+    /// ```
+    pub fn new(tape: Vec<W>, size: usize) -> Network<W> {
+        let machines = (0..size).map(|addr| {
+            let mut comp = IntcodeComp::new(tape.clone());
+            comp.push_input(W::from_i64(addr as i64));
+            comp
+        }).collect();
+        Network {
+            machines,
+            queues: (0..size).map(|_| VecDeque::new()).collect(),
+            partial: (0..size).map(|_| Vec::new()).collect(),
+            rounds: 0,
+        }
+    }
+
+    /// Returns the number of computers.
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    /// Returns true if there are no computers.
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    /// Returns the computer at an address.
+    pub fn machine(&self, addr: usize) -> &IntcodeComp<W> {
+        &self.machines[addr]
+    }
+
+    /// Returns the number of full rounds run so far.
+    pub fn rounds(&self) -> u64 {
+        self.rounds
+    }
+
+    /// Queues a packet for the computer at its destination.
+    pub fn send(&mut self, packet: Packet<W>) -> Result<(), NetError<W>> {
+        match self.address(&packet.dest) {
+            Some(addr) => {
+                self.queues[addr].push_back((packet.x, packet.y));
+                Ok(())
+            },
+            None => Err(NetError::UnknownAddress(packet.dest)),
+        }
+    }
+
+    /// Runs the network until the NAT hook stops it, it goes idle without
+    /// being woken, or every computer halts.  A computer that halts with only
+    /// part of a packet sent is an error.  The hook is called with every
+    /// packet for an address that isn't on the network, in the order they
+    /// were sent, and whenever the network goes idle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use intcode::net::{NetAction, NetError, NetOutcome, Network};
+    ///
+    /// // Each computer reads its address and halts.
+    /// let mut network: Network = Network::new(vec![3,10,99], 2);
+    /// assert_eq!(network.run(|_| NetAction::Continue), Ok(NetOutcome::Halted));
+    /// assert_eq!(network.rounds(), 1);
+    ///
+    /// // Each computer sends half a packet and halts.
+    /// let mut network: Network = Network::new(vec![3,100,104,255,104,1,99], 2);
+    /// assert_eq!(network.run(|_| NetAction::Continue), Err(NetError::TruncatedPacket { addr: 0 }));
+    /// ```
+    pub fn run<F: FnMut(NetEvent<W>) -> NetAction<W>>(&mut self, mut nat: F) -> Result<NetOutcome, NetError<W>> {
+        loop {
+            let mut active = false;
+            let mut running = false;
+            for addr in 0..self.machines.len() {
+                if *self.machines[addr].state() == IntcodeState::Finished {
+                    // Nobody is listening any more.
+                    self.queues[addr].clear();
+                    continue;
+                }
+                let comp = &mut self.machines[addr];
+                match self.queues[addr].pop_front() {
+                    Some((x, y)) => {
+                        comp.push_input(x);
+                        comp.push_input(y);
+                        active = true;
+                    },
+                    None => comp.push_input(W::from_i64(-1)),
+                }
+                comp.start();
+                match *comp.state() {
+                    IntcodeState::Err(ref error) => return Err(NetError::Machine { addr, error: error.clone() }),
+                    IntcodeState::Finished => (),
+                    _ => running = true,
+                }
+                while let Some(v) = comp.pop_output() {
+                    self.partial[addr].push(v);
+                }
+
+                let sent = self.partial[addr].len() / 3 * 3;
+                let packets: Vec<W> = self.partial[addr].drain(..sent).collect();
+                for packet in packets.chunks(3) {
+                    active = true;
+                    let packet = Packet { dest: packet[0].clone(), x: packet[1].clone(), y: packet[2].clone() };
+                    if self.address(&packet.dest).is_some() {
+                        self.send(packet)?;
+                        continue;
+                    }
+                    match nat(NetEvent::Packet(packet)) {
+                        NetAction::Continue => (),
+                        NetAction::Send(p) => self.send(p)?,
+                        NetAction::Stop => return Ok(NetOutcome::Stopped),
+                    }
+                }
+                if !self.partial[addr].is_empty() && *self.machines[addr].state() == IntcodeState::Finished {
+                    return Err(NetError::TruncatedPacket { addr });
+                }
+            }
+            self.rounds += 1;
+
+            if !running {
+                return Ok(NetOutcome::Halted);
+            }
+            if !active {
+                match nat(NetEvent::Idle) {
+                    NetAction::Continue => return Ok(NetOutcome::Idle),
+                    NetAction::Send(p) => self.send(p)?,
+                    NetAction::Stop => return Ok(NetOutcome::Stopped),
+                }
+            }
+        }
+    }
+
+    /// Returns the index of the computer at an address, if there is one.
+    fn address(&self, dest: &W) -> Option<usize> {
+        match dest.to_i64() {
+            Some(d) if d >= 0 && (d as usize) < self.machines.len() => Some(d as usize),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,440 @@
+//! Saves a computer's complete state to a file, and loads it back so a run
+//! can be resumed in a later process.
+//!
+//! The file is plain text, starting with a version line and followed by one
+//! field per line, always in the same order (see write_snapshot() for an
+//! example).  The state line holds one of `ready`, `running`, `needs-input`,
+//! `paused`, `finished`, `breakpoint ADDR`, `watchpoint IP ADDR ACCESS OLD NEW`
+//! or `error KIND HEAD INSTRUCTION REL_BASE [DETAILS...]`, and the overflow
+//! line holds one of `wrap`, `checked` or `saturate`.  Lists are
+//! comma-separated and may be empty.  A computer with sparse memory saves its
+//! tape as `sparse-tape LEN ADDR:VALUE,...` instead, listing only the non-zero
+//! cells.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Access, IntcodeComp, IntcodeError, IntcodeState, MemoryKind, Mode, OverflowPolicy, Program,
+            Snapshot, WatchEvent, Word};
+use super::memory::Memory;
+
+/// The version written by save(), and the only one load() accepts.
+pub const VERSION: u32 = 2;
+
+/// The ways loading a saved state can fail.
+#[derive(Debug)]
+pub enum StateFileError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// The file doesn't start with a supported version line.  The first line
+    /// is included.
+    UnsupportedVersion(String),
+    /// A line couldn't be parsed.  The (1-based) line number and a
+    /// description of the problem are included.
+    Malformed { line: usize, reason: String },
+}
+
+impl fmt::Display for StateFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StateFileError::Io(ref e) => write!(f, "couldn't read state: {}", e),
+            StateFileError::UnsupportedVersion(ref first) =>
+                write!(f, "unsupported state file version: {:?}", first),
+            StateFileError::Malformed { line, ref reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl error::Error for StateFileError {}
+
+impl From<io::Error> for StateFileError {
+    fn from(e: io::Error) -> StateFileError {
+        StateFileError::Io(e)
+    }
+}
+
+/// Formats a field holding a list, leaving off the separating space if the
+/// list is empty.
+fn list_line<'a, W: Word, I: Iterator<Item = &'a W>>(name: &str, values: I) -> String {
+    let strs: Vec<String> = values.map(|v| v.to_string()).collect();
+    if strs.is_empty() {
+        return name.to_string();
+    }
+    format!("{} {}", name, strs.join(","))
+}
+
+fn access_name(a: Access) -> &'static str {
+    match a {
+        Access::Read => "read",
+        Access::Write => "write",
+        Access::ReadWrite => "read-write",
+    }
+}
+
+fn policy_name(p: OverflowPolicy) -> &'static str {
+    match p {
+        OverflowPolicy::Wrap => "wrap",
+        OverflowPolicy::Checked => "checked",
+        OverflowPolicy::Saturate => "saturate",
+    }
+}
+
+fn state_line<W: Word>(state: &IntcodeState<W>) -> String {
+    match *state {
+        IntcodeState::Ready => "ready".to_string(),
+        IntcodeState::Running => "running".to_string(),
+        IntcodeState::NeedsInput => "needs-input".to_string(),
+        IntcodeState::Paused => "paused".to_string(),
+        IntcodeState::Finished => "finished".to_string(),
+        IntcodeState::Breakpoint(addr) => format!("breakpoint {}", addr),
+        IntcodeState::Watchpoint(ref e) => format!("watchpoint {} {} {} {} {}",
+                                               e.ip, e.addr, access_name(e.access), e.old, e.new),
+        IntcodeState::Err(ref e) => {
+            let (kind, details) = match *e {
+                IntcodeError::UnknownOpcode { .. } => ("unknown-opcode", String::new()),
+                IntcodeError::UnknownMode { mode, .. } => ("unknown-mode", format!(" {}", mode)),
+                IntcodeError::ImmediateWrite { .. } => ("immediate-write", String::new()),
+                IntcodeError::NegativeAddress { ref address, .. } =>
+                    ("negative-address", format!(" {}", address)),
+                IntcodeError::AddressOutOfRange { ref address, limit, .. } =>
+                    ("address-out-of-range", format!(" {} {}", address, limit)),
+                IntcodeError::Overflow { operands: (ref x, ref y), .. } =>
+                    ("overflow", format!(" {} {}", x, y)),
+            };
+            format!("error {} {} {} {}{}", kind, e.head(), e.instruction(), e.rel_base(), details)
+        },
+    }
+}
+
+/// Writes a snapshot in the format described in the module documentation.
+/// 
+/// # Example
+/// 
+/// ```
+/// let tape: Vec<i64> = vec![104,5,3,9,1002,9,2,9,99,0];
+/// let mut comp = intcode::IntcodeComp::new(tape);
+/// comp.start();
+/// let mut saved = Vec::new();
+/// intcode::persist::write_snapshot(&comp.snapshot(), &mut saved).unwrap();
+/// assert_eq!(String::from_utf8(saved.clone()).unwrap(), "\
+/// intcode-state 2
+/// head 2
+/// inst_head 2
+/// inst 3
+/// mode 0 0 0
+/// rel_base 0
+/// max_memory 16777216
+/// overflow wrap
+/// state needs-input
+/// inputs
+/// outputs 5
+/// tape 104,5,3,9,1002,9,2,9,99,0
+/// ");
+/// 
+/// let snapshot = intcode::persist::read_snapshot(&saved[..]).unwrap();
+/// assert_eq!(snapshot, comp.snapshot());
+/// 
+/// // Only the non-zero cells of a sparse tape are written.
+/// let tape: Vec<i64> = vec![1101,5,0,100000,99];
+/// let mut comp = intcode::IntcodeComp::new(tape).with_memory(intcode::MemoryKind::Sparse);
+/// comp.start();
+/// let mut saved = Vec::new();
+/// intcode::persist::write_snapshot(&comp.snapshot(), &mut saved).unwrap();
+/// let saved = String::from_utf8(saved).unwrap();
+/// assert!(saved.ends_with("sparse-tape 100001 0:1101,1:5,3:100000,4:99,100000:5\n"));
+/// let snapshot = intcode::persist::read_snapshot(saved.as_bytes()).unwrap();
+/// assert_eq!(snapshot, comp.snapshot());
+/// ```
+pub fn write_snapshot<W: Word, T: Write>(snapshot: &Snapshot<W>, mut w: T) -> io::Result<()> {
+    writeln!(w, "intcode-state {}", VERSION)?;
+    writeln!(w, "head {}", snapshot.head)?;
+    writeln!(w, "inst_head {}", snapshot.inst_head)?;
+    writeln!(w, "inst {}", snapshot.inst)?;
+    writeln!(w, "mode {} {} {}", snapshot.mode[0].digit(), snapshot.mode[1].digit(),
+             snapshot.mode[2].digit())?;
+    writeln!(w, "rel_base {}", snapshot.rel_base)?;
+    writeln!(w, "max_memory {}", snapshot.max_memory)?;
+    writeln!(w, "overflow {}", policy_name(snapshot.overflow))?;
+    writeln!(w, "state {}", state_line(&snapshot.state))?;
+    writeln!(w, "{}", list_line("inputs", snapshot.inputs.iter()))?;
+    writeln!(w, "{}", list_line("outputs", snapshot.outputs.iter()))?;
+    match snapshot.tape {
+        Memory::Dense(ref cells) => writeln!(w, "{}", list_line("tape", cells.iter()))?,
+        Memory::Sparse(_) => {
+            let cells: Vec<String> = snapshot.tape.non_zero().iter()
+                .map(|&(addr, ref value)| format!("{}:{}", addr, value))
+                .collect();
+            writeln!(w, "sparse-tape {} {}", snapshot.tape.len(), cells.join(","))?;
+        },
+    }
+    w.flush()
+}
+
+/// Reads the fields of a state file one line at a time.
+struct Fields {
+    lines: Vec<String>,
+    line: usize,
+}
+
+impl Fields {
+    fn malformed(&self, reason: String) -> StateFileError {
+        StateFileError::Malformed { line: self.line, reason }
+    }
+
+    /// Returns the words following the given field name on the next line.
+    fn field(&mut self, name: &str) -> Result<Vec<String>, StateFileError> {
+        self.line += 1;
+        let text = match self.lines.get(self.line - 1) {
+            Some(text) => text.clone(),
+            None => return Err(self.malformed(format!("missing field '{}'", name))),
+        };
+        let mut words = text.split_whitespace().map(|w| w.to_string());
+        if words.next().as_deref() != Some(name) {
+            return Err(self.malformed(format!("expected field '{}'", name)));
+        }
+        Ok(words.collect())
+    }
+
+    fn parse<T: FromStr>(&self, word: Option<&String>) -> Result<T, StateFileError> {
+        match word {
+            Some(w) => w.parse::<T>().map_err(|_| self.malformed(format!("invalid value '{}'", w))),
+            None => Err(self.malformed("missing value".to_string())),
+        }
+    }
+
+    fn single<T: FromStr>(&mut self, name: &str) -> Result<T, StateFileError> {
+        let words = self.field(name)?;
+        if words.len() > 1 {
+            return Err(self.malformed(format!("too many values for '{}'", name)));
+        }
+        self.parse(words.first())
+    }
+
+    fn list<T: FromStr>(&mut self, name: &str) -> Result<Vec<T>, StateFileError> {
+        let words = self.field(name)?;
+        match words.len() {
+            0 => Ok(Vec::new()),
+            1 => words[0].split(',').map(|v| self.parse(Some(&v.to_string()))).collect(),
+            _ => Err(self.malformed(format!("too many values for '{}'", name))),
+        }
+    }
+
+    /// Reads the last field, which is either a dense or a sparse tape.
+    fn tape<W: Word>(&mut self) -> Result<Memory<W>, StateFileError> {
+        let is_sparse = self.lines.get(self.line)
+            .is_some_and(|text| text.split_whitespace().next() == Some("sparse-tape"));
+        if !is_sparse {
+            return Ok(Memory::Dense(self.list("tape")?));
+        }
+        let words = self.field("sparse-tape")?;
+        if words.len() > 2 {
+            return Err(self.malformed("too many values for 'sparse-tape'".to_string()));
+        }
+        let len: usize = self.parse(words.first())?;
+        let mut tape = Memory::new(MemoryKind::Sparse, Vec::new());
+        if let Some(cells) = words.get(1) {
+            for cell in cells.split(',') {
+                let mut parts = cell.splitn(2, ':').map(|p| p.to_string());
+                let addr: usize = self.parse(parts.next().as_ref())?;
+                if addr >= len {
+                    return Err(self.malformed(format!("address {} is beyond the tape", addr)));
+                }
+                tape.set(addr, self.parse(parts.next().as_ref())?);
+            }
+        }
+        if len > 0 {
+            let last = tape.get(len - 1);
+            tape.set(len - 1, last);
+        }
+        Ok(tape)
+    }
+
+    fn mode(&self, word: Option<&String>) -> Result<Mode, StateFileError> {
+        let digit: i64 = self.parse(word)?;
+        Mode::from_digit(digit).ok_or_else(|| self.malformed(format!("invalid mode {}", digit)))
+    }
+
+    fn overflow(&mut self) -> Result<OverflowPolicy, StateFileError> {
+        let words = self.field("overflow")?;
+        if words.len() > 1 {
+            return Err(self.malformed("too many values for 'overflow'".to_string()));
+        }
+        match words.first().map(|w| w.as_str()) {
+            Some("wrap") => Ok(OverflowPolicy::Wrap),
+            Some("checked") => Ok(OverflowPolicy::Checked),
+            Some("saturate") => Ok(OverflowPolicy::Saturate),
+            Some(w) => Err(self.malformed(format!("unknown overflow policy '{}'", w))),
+            None => Err(self.malformed("missing value".to_string())),
+        }
+    }
+
+    fn state<W: Word>(&mut self) -> Result<IntcodeState<W>, StateFileError> {
+        let words = self.field("state")?;
+        let kind = words.first().map(|w| w.as_str()).unwrap_or("");
+        let state = match kind {
+            "ready" => IntcodeState::Ready,
+            "running" => IntcodeState::Running,
+            "needs-input" => IntcodeState::NeedsInput,
+            "paused" => IntcodeState::Paused,
+            "finished" => IntcodeState::Finished,
+            "breakpoint" => IntcodeState::Breakpoint(self.parse(words.get(1))?),
+            "watchpoint" => IntcodeState::Watchpoint(WatchEvent {
+                ip: self.parse(words.get(1))?,
+                addr: self.parse(words.get(2))?,
+                access: match words.get(3).map(|w| w.as_str()) {
+                    Some("read") => Access::Read,
+                    Some("write") => Access::Write,
+                    Some("read-write") => Access::ReadWrite,
+                    _ => return Err(self.malformed("invalid watchpoint access".to_string())),
+                },
+                old: self.parse(words.get(4))?,
+                new: self.parse(words.get(5))?,
+            }),
+            "error" => IntcodeState::Err(self.error(&words[1..])?),
+            _ => return Err(self.malformed(format!("unknown state '{}'", kind))),
+        };
+        Ok(state)
+    }
+
+    fn error<W: Word>(&self, words: &[String]) -> Result<IntcodeError<W>, StateFileError> {
+        let head: usize = self.parse(words.get(1))?;
+        let instruction: W = self.parse(words.get(2))?;
+        let rel_base: i64 = self.parse(words.get(3))?;
+        let e = match words.first().map(|w| w.as_str()) {
+            Some("unknown-opcode") => IntcodeError::UnknownOpcode { head, instruction, rel_base },
+            Some("unknown-mode") =>
+                IntcodeError::UnknownMode { head, instruction, rel_base, mode: self.parse(words.get(4))? },
+            Some("immediate-write") => IntcodeError::ImmediateWrite { head, instruction, rel_base },
+            Some("negative-address") =>
+                IntcodeError::NegativeAddress { head, instruction, rel_base, address: self.parse(words.get(4))? },
+            Some("address-out-of-range") => IntcodeError::AddressOutOfRange {
+                head,
+                instruction,
+                rel_base,
+                address: self.parse(words.get(4))?,
+                limit: self.parse(words.get(5))?,
+            },
+            Some("overflow") => IntcodeError::Overflow {
+                head,
+                instruction,
+                rel_base,
+                operands: (self.parse(words.get(4))?, self.parse(words.get(5))?),
+            },
+            _ => return Err(self.malformed("unknown error kind".to_string())),
+        };
+        Ok(e)
+    }
+}
+
+/// Reads a snapshot written by write_snapshot().
+pub fn read_snapshot<W: Word, R: Read>(r: R) -> Result<Snapshot<W>, StateFileError> {
+    let lines = BufReader::new(r).lines().collect::<Result<Vec<String>, io::Error>>()?;
+    let first = lines.first().cloned().unwrap_or_default();
+    if first.trim() != format!("intcode-state {}", VERSION) {
+        return Err(StateFileError::UnsupportedVersion(first));
+    }
+    let mut f = Fields { lines, line: 1 };
+    let head = f.single("head")?;
+    let inst_head = f.single("inst_head")?;
+    let inst = f.single("inst")?;
+    let words = f.field("mode")?;
+    let mode = [f.mode(words.first())?, f.mode(words.get(1))?, f.mode(words.get(2))?];
+    let rel_base = f.single("rel_base")?;
+    let max_memory = f.single("max_memory")?;
+    let overflow = f.overflow()?;
+    let state = f.state()?;
+    let inputs = f.list("inputs")?;
+    let outputs = f.list("outputs")?;
+    let tape = f.tape()?;
+    Ok(Snapshot {
+        tape,
+        head,
+        inst_head,
+        inst,
+        mode,
+        rel_base,
+        max_memory,
+        overflow,
+        state,
+        inputs: inputs.into_iter().collect(),
+        outputs: outputs.into_iter().collect(),
+    })
+}
+
+impl<W: Word> IntcodeComp<W> {
+    /// Saves the computer's state to a file, so it can be picked up later
+    /// with IntcodeComp::load().  The tape is saved sparsely if the computer
+    /// has sparse memory.  Breakpoints, watchpoints and the tracer aren't
+    /// saved.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let path = std::env::temp_dir().join("intcode-save-example.state");
+    /// let tape: Vec<i64> = vec![3,9,1002,9,2,9,4,9,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.start();
+    /// comp.save(&path).unwrap();
+    /// 
+    /// let mut resumed: intcode::IntcodeComp = intcode::IntcodeComp::load(&path).unwrap();
+    /// assert_eq!(*resumed.state(), intcode::IntcodeState::NeedsInput);
+    /// resumed.push_input(21);
+    /// resumed.start();
+    /// assert_eq!(resumed.pop_output(), Some(42));
+    /// 
+    /// // The overflow policy is saved too.
+    /// let tape: Vec<i64> = vec![3,9,1002,9,2,9,4,9,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape)
+    ///     .with_overflow_policy(intcode::OverflowPolicy::Checked);
+    /// comp.start();
+    /// comp.save(&path).unwrap();
+    /// 
+    /// let mut resumed: intcode::IntcodeComp = intcode::IntcodeComp::load(&path).unwrap();
+    /// resumed.push_input(i64::MAX);
+    /// resumed.start();
+    /// assert!(matches!(*resumed.state(), intcode::IntcodeState::Err(
+    ///     intcode::IntcodeError::Overflow { head: 2, .. })));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_snapshot(&self.snapshot(), BufWriter::new(File::create(path)?))
+    }
+
+    /// Loads a computer saved by save().  It carries on exactly where the
+    /// saved computer left off.  A state file doesn't hold the tape the saved
+    /// computer started with, so the tape as saved becomes the one reset()
+    /// goes back to.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// let path = std::env::temp_dir().join("intcode-load-example.state");
+    /// // This tape overwrites its first instruction with 10, then waits for
+    /// // input.
+    /// let tape: Vec<i64> = vec![1101,4,6,0,3,7,99,0];
+    /// let mut comp = intcode::IntcodeComp::new(tape);
+    /// comp.start();
+    /// comp.save(&path).unwrap();
+    /// 
+    /// let mut loaded: intcode::IntcodeComp = intcode::IntcodeComp::load(&path).unwrap();
+    /// loaded.push_input(1);
+    /// loaded.start();
+    /// loaded.reset();
+    /// assert_eq!(loaded.head(), 0);
+    /// assert_eq!(loaded.get(0), 10);
+    /// assert_eq!(loaded.get(7), 0);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<IntcodeComp<W>, StateFileError> {
+        let snapshot = read_snapshot(File::open(path)?)?;
+        let mut comp = IntcodeComp::new(Vec::new());
+        comp.restore(&snapshot);
+        comp.program = Program::of(&comp.tape);
+        Ok(comp)
+    }
+}
@@ -0,0 +1,231 @@
+//! Chains of computers, each feeding its output to the next.
+//!
+//! A Pipeline holds a list of stages.  Everything a stage outputs is queued as
+//! input for the stage after it.  In a linear pipeline the last stage's output
+//! is collected for the caller, and in a ring it's also fed back to the first
+//! stage.  Each stage can be given initial inputs, such as a phase setting,
+//! which it reads before anything passed along the chain.
+//!
+//! Pipeline::run() runs the stages in order, over and over, until a full pass
+//! passes no values between them.  Since every value is passed on as soon as
+//! the stage that made it stops, the result doesn't depend on how the stages
+//! are scheduled.
+
+use std::error;
+use std::fmt;
+
+use super::{IntcodeComp, IntcodeError, IntcodeState, Word};
+
+/// How the stages of a pipeline are connected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Topology {
+    /// Each stage feeds the next, and the last stage's output is collected.
+    Linear,
+    /// Like Linear, but the last stage also feeds the first.
+    Ring,
+}
+
+/// Why Pipeline::run() returned.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PipelineStop<W = i64> {
+    /// Every stage halted.
+    Halted,
+    /// A stage is waiting for input that nothing in the pipeline will give
+    /// it.  The (0-based) index of the first such stage, and of every stage
+    /// that has halted, are included.  In a linear pipeline, a starved first
+    /// stage just needs more input from the caller.
+    Starved { stage: usize, halted: Vec<usize> },
+    /// A stage stopped for some other reason, such as a breakpoint, a
+    /// watchpoint or its instruction budget running out.  The (0-based)
+    /// index of the first such stage, and its state, are included.  Running
+    /// the pipeline again resumes it.
+    Stopped { stage: usize, state: IntcodeState<W> },
+}
+
+/// The result of running a pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineReport<W = i64> {
+    /// Everything the last stage output during the run, in order.  In a ring
+    /// these were also passed to the first stage.
+    pub outputs: Vec<W>,
+    /// Why the pipeline stopped.
+    pub stop: PipelineStop<W>,
+}
+
+/// A stage of a pipeline failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineError<W = i64> {
+    /// The (0-based) index of the stage.
+    pub stage: usize,
+    /// The stage's error.
+    pub error: IntcodeError<W>,
+}
+
+impl<W: Word> fmt::Display for PipelineError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stage {} failed: {}", self.stage, self.error)
+    }
+}
+
+impl<W: Word> error::Error for PipelineError<W> {}
+
+/// A chain of computers, see the module documentation.
+pub struct Pipeline<W = i64> {
+    stages: Vec<IntcodeComp<W>>,
+    topology: Topology,
+}
+
+impl<W: Word> Pipeline<W> {
+    /// Creates a pipeline of the given number of stages, each running a copy
+    /// of the tape.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use intcode::pipeline::{Pipeline, PipelineStop, Topology};
+    ///
+    /// // This tape outputs 10 times its first input plus its second.
+    /// let tape: Vec<i64> = vec![3,15,3,16,1002,15,10,15,1,15,16,15,4,15,99,0,0];
+    /// let mut pipeline = Pipeline::new(tape, 3, Topology::Linear)
+    ///     .with_initial_inputs(vec![1, 2, 3]);
+    /// let report = pipeline.run(&[0]).unwrap();
+    /// assert_eq!(report.outputs, vec![60]);
+    /// assert_eq!(report.stop, PipelineStop::Halted);
+    ///
+    /// // Without a first input the first stage starves.
+    /// let tape: Vec<i64> = vec![3,15,3,16,1002,15,10,15,1,15,16,15,4,15,99,0,0];
+    /// let mut pipeline = Pipeline::new(tape, 2, Topology::Linear);
+    /// let report = pipeline.run(&[4]).unwrap();
+    /// assert_eq!(report.stop, PipelineStop::Starved { stage: 0, halted: vec![] });
+    /// let report = pipeline.run(&[5]).unwrap();
+    /// assert_eq!(report.stop, PipelineStop::Starved { stage: 1, halted: vec![0] });
+    /// ```
+    pub fn new(tape: Vec<W>, stages: usize, topology: Topology) -> Pipeline<W> {
+        let stages = (0..stages).map(|_| IntcodeComp::new(tape.clone())).collect();
+        Pipeline::from_stages(stages, topology)
+    }
+
+    /// Creates a pipeline from computers that have already been set up, for
+    /// stages that run different tapes or need other options.  The computers
+    /// shouldn't have an input source or output sink attached, since the
+    /// pipeline passes values through their queues.
+    pub fn from_stages(stages: Vec<IntcodeComp<W>>, topology: Topology) -> Pipeline<W> {
+        Pipeline { stages, topology }
+    }
+
+    /// Queues one initial input for each stage, in order, such as a phase
+    /// setting.  Stages past the end of the inputs get none.
+    pub fn with_initial_inputs<I: IntoIterator<Item = W>>(mut self, inputs: I) -> Pipeline<W> {
+        for (stage, value) in self.stages.iter_mut().zip(inputs) {
+            stage.push_input(value);
+        }
+        self
+    }
+
+    /// Queues an input for a single stage.
+    pub fn push_input(&mut self, stage: usize, value: W) {
+        self.stages[stage].push_input(value);
+    }
+
+    /// Returns the number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Returns true if there are no stages.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Returns a stage.
+    pub fn stage(&self, stage: usize) -> &IntcodeComp<W> {
+        &self.stages[stage]
+    }
+
+    /// Passes some input to the first stage, and runs the pipeline until it
+    /// can't go any further.  A linear pipeline can be run again with more
+    /// input, and any pipeline can be run again after a stage stops for a
+    /// reason other than needing input.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use intcode::{IntcodeComp, IntcodeState};
+    /// use intcode::pipeline::{Pipeline, PipelineStop, Topology};
+    ///
+    /// // This tape starts a total at its first input, then adds each of the
+    /// // next five to it, outputting the total each time.
+    /// let tape: Vec<i64> = vec![3,30,1101,0,5,31,3,32,1,30,32,30,4,30,1001,31,-1,31,
+    ///                           1005,31,6,99];
+    /// let mut ring = Pipeline::new(tape, 2, Topology::Ring)
+    ///     .with_initial_inputs(vec![1, 2]);
+    /// let report = ring.run(&[0]).unwrap();
+    /// assert_eq!(report.outputs, vec![3, 7, 18, 47, 123]);
+    /// assert_eq!(report.stop, PipelineStop::Halted);
+    ///
+    /// // A stage at a breakpoint holds up the pipeline until it's run again.
+    /// let tape: Vec<i64> = vec![3,15,3,16,1002,15,10,15,1,15,16,15,4,15,99,0,0];
+    /// let mut first = IntcodeComp::new(tape.clone());
+    /// first.add_breakpoint(12);
+    /// let stages = vec![first, IntcodeComp::new(tape)];
+    /// let mut pipeline = Pipeline::from_stages(stages, Topology::Linear)
+    ///     .with_initial_inputs(vec![1, 2]);
+    /// let report = pipeline.run(&[4]).unwrap();
+    /// assert_eq!(report.stop, PipelineStop::Stopped { stage: 0, state: IntcodeState::Breakpoint(12) });
+    /// let report = pipeline.run(&[]).unwrap();
+    /// assert_eq!(report.outputs, vec![34]);
+    /// assert_eq!(report.stop, PipelineStop::Halted);
+    /// ```
+    pub fn run(&mut self, input: &[W]) -> Result<PipelineReport<W>, PipelineError<W>> {
+        let mut outputs = Vec::new();
+        if self.stages.is_empty() {
+            outputs.extend_from_slice(input);
+            return Ok(PipelineReport { outputs, stop: PipelineStop::Halted });
+        }
+        for v in input {
+            self.stages[0].push_input(v.clone());
+        }
+
+        let last = self.stages.len() - 1;
+        loop {
+            let mut moved = false;
+            for i in 0..self.stages.len() {
+                let next = (i + 1) % self.stages.len();
+                self.stages[i].start();
+                if let IntcodeState::Err(ref error) = *self.stages[i].state() {
+                    return Err(PipelineError { stage: i, error: error.clone() });
+                }
+                while let Some(v) = self.stages[i].pop_output() {
+                    if i == last {
+                        outputs.push(v.clone());
+                        if self.topology == Topology::Linear {
+                            continue;
+                        }
+                    }
+                    moved = true;
+                    self.stages[next].push_input(v);
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+
+        // A stopped stage may be why others are starved, so it's reported
+        // first.
+        let stopped = self.stages.iter()
+            .position(|s| !matches!(*s.state(), IntcodeState::Finished | IntcodeState::NeedsInput));
+        if let Some(stage) = stopped {
+            let state = self.stages[stage].state().clone();
+            return Ok(PipelineReport { outputs, stop: PipelineStop::Stopped { stage, state } });
+        }
+        let halted: Vec<usize> = (0..self.stages.len())
+            .filter(|&i| *self.stages[i].state() == IntcodeState::Finished)
+            .collect();
+        let stop = match (0..self.stages.len()).find(|i| !halted.contains(i)) {
+            Some(stage) => PipelineStop::Starved { stage, halted },
+            None => PipelineStop::Halted,
+        };
+        Ok(PipelineReport { outputs, stop })
+    }
+}
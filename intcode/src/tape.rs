@@ -0,0 +1,127 @@
+//! Reads tapes from text.
+//!
+//! A tape is a list of comma-separated numbers.  The parser is forgiving
+//! about layout: values may be spread over several lines, whitespace
+//! (including CRLF line endings) around values is ignored, and a comment
+//! starting with `;` runs to the end of its line.  A line break also
+//! separates values, and a line may end with a trailing comma.  Blank lines
+//! are skipped.
+//!
+//! ```text
+//! 1,9,10,3,   ; add
+//! 2,3,11,0    ; multiply
+//! 99
+//! 30,40,50
+//! ```
+//!
+//! load_tape() also reads tapes in the binary format, see the binary module.
+
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::binary::{self, BinaryTapeError};
+
+/// The ways reading a tape can fail.
+#[derive(Debug)]
+pub enum TapeParseError {
+    /// The file couldn't be read.
+    Io(io::Error),
+    /// A field isn't a valid number.  The field's (0-based) index, which is
+    /// where it would have gone on the tape, its (1-based) line and column,
+    /// and its text are included.  The text of an empty field is empty.
+    InvalidField { index: usize, line: usize, column: usize, text: String },
+    /// The file is a binary tape, and it's corrupt or unsupported.
+    Binary(BinaryTapeError),
+}
+
+impl fmt::Display for TapeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TapeParseError::Io(ref e) => write!(f, "couldn't read tape: {}", e),
+            TapeParseError::InvalidField { index, line, column, ref text } => {
+                write!(f, "field {} (line {}, column {}): ", index, line, column)?;
+                if text.is_empty() {
+                    write!(f, "missing value")
+                } else {
+                    write!(f, "invalid value '{}'", text)
+                }
+            },
+            TapeParseError::Binary(ref e) => write!(f, "couldn't read binary tape: {}", e),
+        }
+    }
+}
+
+impl error::Error for TapeParseError {}
+
+impl From<io::Error> for TapeParseError {
+    fn from(e: io::Error) -> TapeParseError {
+        TapeParseError::Io(e)
+    }
+}
+
+impl From<BinaryTapeError> for TapeParseError {
+    fn from(e: BinaryTapeError) -> TapeParseError {
+        TapeParseError::Binary(e)
+    }
+}
+
+/// Parses a tape in the format described in the module documentation.
+///
+/// # Example
+///
+/// ```
+/// let text = "1,9,10,3,   ; add\r\n2,3,11,0\r\n99\r\n30,40,50\r\n";
+/// assert_eq!(intcode::parse_tape(text).unwrap(), vec![1,9,10,3,2,3,11,0,99,30,40,50]);
+///
+/// let err = intcode::parse_tape("1,2,\n3,x4,5").unwrap_err();
+/// assert_eq!(err.to_string(), "field 3 (line 2, column 3): invalid value 'x4'");
+/// let err = intcode::parse_tape("1,,2").unwrap_err();
+/// assert_eq!(err.to_string(), "field 1 (line 1, column 3): missing value");
+/// ```
+pub fn parse_tape(text: &str) -> Result<Vec<i64>, TapeParseError> {
+    let mut tape = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let code = match line.find(';') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+        if code.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = code.split(',').collect();
+        let mut offset = 0;
+        for (i, field) in fields.iter().enumerate() {
+            let start = offset + (field.len() - field.trim_start().len());
+            offset += field.len() + 1;
+            let value = field.trim();
+            if value.is_empty() && i == fields.len() - 1 && i > 0 {
+                // A trailing comma at the end of the line.
+                continue;
+            }
+            match value.parse::<i64>() {
+                Ok(v) => tape.push(v),
+                Err(_) => return Err(TapeParseError::InvalidField {
+                    index: tape.len(),
+                    line: line_index + 1,
+                    column: line[..start].chars().count() + 1,
+                    text: value.to_string(),
+                }),
+            }
+        }
+    }
+    Ok(tape)
+}
+
+/// Reads a tape file.  Files starting with binary::MAGIC are decoded as
+/// binary tapes, and anything else is parsed as text, see parse_tape().
+pub fn load_tape<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, TapeParseError> {
+    let bytes = fs::read(path)?;
+    if bytes.starts_with(&binary::MAGIC) {
+        return Ok(binary::decode(&bytes)?);
+    }
+    let text = String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    parse_tape(&text)
+}
@@ -0,0 +1,215 @@
+//! Records every instruction an IntcodeComp executes.
+//!
+//! Each executed instruction produces one line, in one of two formats:
+//!  * Text - `HEAD MNEMONIC OPERANDS WRITE rb=REL_BASE`, e.g.
+//!    `0004 MUL 3,11,9 [9]=33 rb=0`.  Operands are comma-separated, and both
+//!    operands and the write are shown as `-` if there are none.
+//!  * Json - one object per line, e.g.
+//!    `{"head":4,"op":"MUL","operands":[3,11,9],"write":{"addr":9,"value":33},"rel_base":0}`.
+//!    `write` is `null` if nothing was written.
+//!
+//! Operands are resolved: input parameters show the value the instruction
+//! used, and output parameters show the address written to.  The relative
+//! base is the one in effect after the instruction finished.  Both formats are
+//! stable, so traces of two runs can be compared with a plain diff.
+//!
+//! An instruction that fails still gets a line, showing whatever it resolved
+//! before failing, with the error added: ` error: MESSAGE` at the end of a
+//! Text line, or an `"error"` string in a Json object.  If the instruction
+//! couldn't even be decoded, its mnemonic is shown as `???` (or `null`).
+
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+use super::Opcode;
+
+/// The line format a Tracer writes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceFormat {
+    Text,
+    Json,
+}
+
+/// Everything recorded about a single executed instruction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceRecord<W = i64> {
+    /// The position of the instruction.
+    pub head: usize,
+    /// The instruction's opcode.
+    pub opcode: Opcode,
+    /// The resolved parameters, see the module documentation.
+    pub operands: Vec<W>,
+    /// The address and value written by the instruction, if any.
+    pub write: Option<(usize, W)>,
+    /// The relative base after the instruction.
+    pub rel_base: i64,
+}
+
+impl<W: fmt::Display> TraceRecord<W> {
+    /// Returns the record in the Json format.
+    /// 
+    /// # Example
+    /// 
+    /// ```
+    /// use intcode::Opcode;
+    /// use intcode::trace::TraceRecord;
+    /// let record = TraceRecord {
+    ///     head: 4, opcode: Opcode::Mult, operands: vec![3,11,9], write: Some((9, 33)), rel_base: 0 };
+    /// assert_eq!(record.to_json(),
+    ///            r#"{"head":4,"op":"MUL","operands":[3,11,9],"write":{"addr":9,"value":33},"rel_base":0}"#);
+    /// assert_eq!(record.to_string(), "0004 MUL 3,11,9 [9]=33 rb=0");
+    /// ```
+    pub fn to_json(&self) -> String {
+        let operands: Vec<String> = self.operands.iter().map(|o| o.to_string()).collect();
+        let write = match self.write {
+            Some((addr, ref value)) => format!("{{\"addr\":{},\"value\":{}}}", addr, value),
+            None => "null".to_string(),
+        };
+        format!("{{\"head\":{},\"op\":\"{}\",\"operands\":[{}],\"write\":{},\"rel_base\":{}}}",
+                self.head, self.opcode.mnemonic(), operands.join(","), write, self.rel_base)
+    }
+}
+
+/// Formats the record in the Text format.
+impl<W: fmt::Display> fmt::Display for TraceRecord<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04} {} ", self.head, self.opcode.mnemonic())?;
+        if self.operands.is_empty() {
+            write!(f, "-")?;
+        } else {
+            let operands: Vec<String> = self.operands.iter().map(|o| o.to_string()).collect();
+            write!(f, "{}", operands.join(","))?;
+        }
+        match self.write {
+            Some((addr, ref value)) => write!(f, " [{}]={}", addr, value)?,
+            None => write!(f, " -")?,
+        }
+        write!(f, " rb={}", self.rel_base)
+    }
+}
+
+/// Writes a TraceRecord for every instruction executed by the computer it's
+/// attached to, see IntcodeComp::set_tracer().
+pub struct Tracer<W = i64> {
+    sink: Box<dyn Write>,
+    format: TraceFormat,
+    /// The record for the instruction currently being executed.
+    record: TraceRecord<W>,
+    /// True once begin() has been called for the current instruction.
+    started: bool,
+    /// The first error returned by the sink.  Nothing more is written after
+    /// an error.
+    error: Option<io::Error>,
+}
+
+impl<W: fmt::Display> Tracer<W> {
+    /// Creates a tracer that writes lines in the given format to a sink.
+    /// Lines are written as instructions execute, so wrap slow sinks in a
+    /// BufWriter.
+    pub fn new<T: Write + 'static>(sink: T, format: TraceFormat) -> Tracer<W> {
+        Tracer {
+            sink: Box::new(sink),
+            format,
+            record: TraceRecord {
+                head: 0,
+                opcode: Opcode::Halt,
+                operands: Vec::new(),
+                write: None,
+                rel_base: 0,
+            },
+            started: false,
+            error: None,
+        }
+    }
+
+    /// Returns the error that stopped the tracer from writing, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Starts recording a new instruction.
+    pub(crate) fn begin(&mut self, head: usize, opcode: Opcode) {
+        self.record.head = head;
+        self.record.opcode = opcode;
+        self.record.operands.clear();
+        self.record.write = None;
+        self.started = true;
+    }
+
+    /// Records a resolved parameter of the current instruction.
+    pub(crate) fn operand(&mut self, value: W) {
+        self.record.operands.push(value);
+    }
+
+    /// Records a write made by the current instruction.
+    pub(crate) fn write(&mut self, addr: usize, value: W) {
+        self.record.write = Some((addr, value));
+    }
+
+    /// Finishes the current instruction and writes its line.
+    pub(crate) fn finish(&mut self, rel_base: i64) {
+        self.started = false;
+        if self.error.is_some() {
+            return;
+        }
+        self.record.rel_base = rel_base;
+        let result = match self.format {
+            TraceFormat::Text => writeln!(self.sink, "{}", self.record),
+            TraceFormat::Json => writeln!(self.sink, "{}", self.record.to_json()),
+        };
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    /// Forgets the current instruction without writing a line, since it
+    /// wasn't executed after all.
+    pub(crate) fn cancel(&mut self) {
+        self.started = false;
+    }
+
+    /// Finishes the instruction at the given head after it failed, and
+    /// writes what was recorded of it along with the error.
+    pub(crate) fn fail<E: fmt::Display>(&mut self, head: usize, rel_base: i64, error: &E) {
+        let started = self.started;
+        self.started = false;
+        if self.error.is_some() {
+            return;
+        }
+        self.record.rel_base = rel_base;
+        let result = match (self.format, started) {
+            (TraceFormat::Text, true) => writeln!(self.sink, "{} error: {}", self.record, error),
+            (TraceFormat::Text, false) => writeln!(self.sink, "{:04} ??? - - rb={} error: {}", head, rel_base, error),
+            (TraceFormat::Json, true) => {
+                let json = self.record.to_json();
+                writeln!(self.sink, "{},\"error\":\"{}\"}}", &json[..json.len() - 1], escape(error))
+            },
+            (TraceFormat::Json, false) => writeln!(self.sink,
+                "{{\"head\":{},\"op\":null,\"operands\":[],\"write\":null,\"rel_base\":{},\"error\":\"{}\"}}",
+                head, rel_base, escape(error)),
+        };
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    /// Flushes the sink.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush()
+    }
+}
+
+/// Formats a value as the inside of a JSON string.
+fn escape<E: fmt::Display>(value: &E) -> String {
+    let mut escaped = String::new();
+    for c in value.to_string().chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
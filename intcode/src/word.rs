@@ -0,0 +1,133 @@
+//! The types a computer's memory can be made of.
+//!
+//! IntcodeComp works on i64 cells by default, but can run with any Word.
+//! i64 and i128 are always available.  Building with the `bigint` feature adds
+//! BigInt, whose cells can never overflow.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "bigint")] {
+//! use intcode::word::BigInt;
+//!
+//! // This tape squares its input.
+//! let tape: Vec<BigInt> = [3,9,2,9,9,9,4,9,99,0].iter().map(|&v| BigInt::from(v)).collect();
+//! let mut comp = intcode::IntcodeComp::new(tape);
+//! comp.push_input(BigInt::from(u64::MAX));
+//! comp.start();
+//! assert_eq!(comp.pop_output().unwrap().to_string(), "340282366920938463426481119284349108225");
+//! # }
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "bigint")]
+pub use num_bigint::BigInt;
+#[cfg(feature = "bigint")]
+use num_traits::ToPrimitive;
+
+/// A single cell of a computer's memory.
+pub trait Word: Clone + fmt::Debug + fmt::Display + PartialOrd + FromStr + 'static {
+    /// Converts an i64 into a word.  Every word type must be able to hold any
+    /// i64.
+    fn from_i64(value: i64) -> Self;
+
+    /// Converts the word into an i64, if it fits.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// Adds two words, wrapping around at the bounds of the type.
+    fn wrapping_add(&self, other: &Self) -> Self;
+
+    /// Multiplies two words, wrapping around at the bounds of the type.
+    fn wrapping_mul(&self, other: &Self) -> Self;
+
+    /// Adds two words, returning None if the result doesn't fit.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Multiplies two words, returning None if the result doesn't fit.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    /// Adds two words, clamping the result to the bounds of the type.
+    fn saturating_add(&self, other: &Self) -> Self;
+
+    /// Multiplies two words, clamping the result to the bounds of the type.
+    fn saturating_mul(&self, other: &Self) -> Self;
+}
+
+macro_rules! primitive_word {
+    ($t:ty) => {
+        impl Word for $t {
+            fn from_i64(value: i64) -> $t {
+                <$t>::from(value)
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            fn wrapping_add(&self, other: &$t) -> $t {
+                <$t>::wrapping_add(*self, *other)
+            }
+
+            fn wrapping_mul(&self, other: &$t) -> $t {
+                <$t>::wrapping_mul(*self, *other)
+            }
+
+            fn checked_add(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn saturating_add(&self, other: &$t) -> $t {
+                <$t>::saturating_add(*self, *other)
+            }
+
+            fn saturating_mul(&self, other: &$t) -> $t {
+                <$t>::saturating_mul(*self, *other)
+            }
+        }
+    };
+}
+
+primitive_word!(i64);
+primitive_word!(i128);
+
+#[cfg(feature = "bigint")]
+impl Word for BigInt {
+    fn from_i64(value: i64) -> BigInt {
+        BigInt::from(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn wrapping_add(&self, other: &BigInt) -> BigInt {
+        self + other
+    }
+
+    fn wrapping_mul(&self, other: &BigInt) -> BigInt {
+        self * other
+    }
+
+    fn checked_add(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self * other)
+    }
+
+    fn saturating_add(&self, other: &BigInt) -> BigInt {
+        self + other
+    }
+
+    fn saturating_mul(&self, other: &BigInt) -> BigInt {
+        self * other
+    }
+}